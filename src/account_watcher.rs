@@ -0,0 +1,336 @@
+use crate::{
+    query::{Query, QueryCryptoGetAccountBalance, QueryCryptoGetAccountRecords, QueryResponse},
+    AccountId, Client, ErrorKind, TransactionRecord,
+};
+use failure::Error;
+use futures::{stream, Stream};
+use std::{
+    collections::{HashSet, VecDeque},
+    pin::Pin,
+    task::{Context, Poll},
+    time::{Duration, Instant},
+};
+use tokio::timer::delay;
+
+#[derive(Debug, Clone)]
+pub enum AccountUpdate {
+    /// The balance of the account, in tinybars, changed.
+    Balance(u64),
+
+    /// A record for a transaction involving the account was seen for the first time.
+    Record(TransactionRecord),
+}
+
+/// A stream of balance changes and new transaction records for an account.
+///
+/// The account is polled with a balance query and an account records query on every
+/// interval. Records that already exist when the first poll runs are not emitted.
+///
+/// Both queries are paid for by the operator, so each poll costs the cost of a balance query
+/// and of an account records query. With a maximum query payment on the client, or a
+/// `budget`, the cost of each query is looked up first, which doubles the requests sent to
+/// the node; each query is then held to that maximum, or to what is left of the budget.
+pub struct AccountWatcher<'a> {
+    client: &'a Client,
+    account: AccountId,
+    interval: Duration,
+    budget: Option<u64>,
+    stream: Option<Pin<Box<dyn Stream<Item = Result<AccountUpdate, Error>> + Send + 'a>>>,
+}
+
+impl<'a> AccountWatcher<'a> {
+    pub(crate) fn new(client: &'a Client, account: AccountId) -> Self {
+        Self {
+            client,
+            account,
+            interval: Duration::from_secs(5),
+            budget: None,
+            stream: None,
+        }
+    }
+
+    /// How long to wait between polls of the account. Defaults to 5 seconds.
+    pub fn interval(mut self, interval: Duration) -> Self {
+        self.interval = interval;
+        self
+    }
+
+    /// The total amount, in tinybars, that may be paid for queries over the life of the watcher.
+    ///
+    /// When set, the cost of each query is looked up before paying for it and the stream
+    /// ends with `ErrorKind::MaxQueryPaymentExceeded` once the budget cannot cover a query.
+    pub fn budget(mut self, budget: u64) -> Self {
+        self.budget = Some(budget);
+        self
+    }
+}
+
+impl<'a> Stream for AccountWatcher<'a> {
+    type Item = Result<AccountUpdate, Error>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        if self.stream.is_none() {
+            let stream = updates(self.client, self.account, self.interval, self.budget);
+            self.stream = Some(Box::pin(stream));
+        }
+
+        // note: cannot fail
+        self.stream.as_mut().unwrap().as_mut().poll_next(cx)
+    }
+}
+
+struct State<'a> {
+    client: &'a Client,
+    account: AccountId,
+    interval: Duration,
+    budget: Option<u64>,
+    balance: Option<u64>,
+    // The hashes of the records returned by the last poll
+    seen: Option<HashSet<Vec<u8>>>,
+    pending: VecDeque<AccountUpdate>,
+    done: bool,
+}
+
+fn updates<'a>(
+    client: &'a Client,
+    account: AccountId,
+    interval: Duration,
+    budget: Option<u64>,
+) -> impl Stream<Item = Result<AccountUpdate, Error>> + Send + 'a {
+    let state = State {
+        client,
+        account,
+        interval,
+        budget,
+        balance: None,
+        seen: None,
+        pending: VecDeque::new(),
+        done: false,
+    };
+
+    stream::unfold(state, |mut state| {
+        async move {
+            loop {
+                if let Some(update) = state.pending.pop_front() {
+                    return Some((Ok(update), state));
+                }
+
+                if state.done {
+                    return None;
+                }
+
+                if state.seen.is_some() {
+                    delay(Instant::now() + state.interval).await;
+                }
+
+                if let Err(error) = state.poll().await {
+                    state.done = true;
+                    return Some((Err(error), state));
+                }
+            }
+        }
+    })
+}
+
+impl<'a> State<'a> {
+    async fn poll(&mut self) -> Result<(), Error> {
+        let balance = self
+            .get(QueryCryptoGetAccountBalance::new(self.client, self.account))
+            .await?;
+
+        if self.balance != Some(balance) {
+            self.balance = Some(balance);
+            self.pending.push_back(AccountUpdate::Balance(balance));
+        }
+
+        let records = self
+            .get(QueryCryptoGetAccountRecords::new(self.client, self.account))
+            .await?;
+
+        let hashes = records
+            .iter()
+            .map(|record| record.transaction_hash.clone())
+            .collect();
+
+        // The first poll only establishes which records already exist
+        if let Some(seen) = &self.seen {
+            for record in records {
+                if !seen.contains(&record.transaction_hash) {
+                    self.pending.push_back(AccountUpdate::Record(record));
+                }
+            }
+        }
+
+        // Only the records the node still returns are remembered, so this is bounded by
+        // the period the node keeps records for
+        self.seen = Some(hashes);
+
+        Ok(())
+    }
+
+    async fn get<T>(&mut self, mut query: Query<T>) -> Result<T::Response, Error>
+    where
+        T: QueryResponse + Send + Sync + 'static,
+    {
+        if let Some(budget) = self.budget {
+            let cost = query.cost_async().await?;
+            if cost > budget {
+                Err(ErrorKind::MaxQueryPaymentExceeded {
+                    query_cost: cost,
+                    max_query_payment: budget,
+                })?;
+            }
+
+            self.budget = Some(budget - cost);
+            query.pay(cost);
+        }
+
+        query.get_async().await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::AccountUpdate;
+    use crate::{
+        proto::{self, Query::Query_oneof_query, QueryHeader::ResponseType},
+        transport::Transport,
+        AccountId, Client, ErrorKind, SecretKey,
+    };
+    use failure::Error;
+    use futures::{
+        future::{self, BoxFuture, FutureExt},
+        StreamExt,
+    };
+    use parking_lot::Mutex;
+    use std::time::Duration;
+
+    const COST: u64 = 10;
+
+    // Answers each poll with the next balance and the hashes of the records at that time;
+    // every query costs `COST`
+    struct Polls(Mutex<Vec<(u64, Vec<&'static [u8]>)>>);
+
+    impl Transport for Polls {
+        fn submit(
+            &self,
+            _: proto::Transaction::Transaction,
+            _: &[(String, String)],
+        ) -> BoxFuture<'static, Result<proto::TransactionResponse::TransactionResponse, Error>>
+        {
+            unimplemented!()
+        }
+
+        fn query(
+            &self,
+            query: proto::Query::Query,
+            _: &[(String, String)],
+        ) -> BoxFuture<'static, Result<proto::Response::Response, Error>> {
+            let mut response = proto::Response::Response::new();
+            let mut polls = self.0.lock();
+
+            match query.query {
+                Some(Query_oneof_query::cryptogetAccountBalance(query)) => {
+                    let res = response.mut_cryptogetAccountBalance();
+
+                    if query.get_header().get_responseType() == ResponseType::COST_ANSWER {
+                        res.mut_header().set_cost(COST);
+                    } else {
+                        res.set_balance(polls[0].0);
+                    }
+                }
+
+                Some(Query_oneof_query::cryptoGetAccountRecords(query)) => {
+                    let res = response.mut_cryptoGetAccountRecords();
+
+                    if query.get_header().get_responseType() == ResponseType::COST_ANSWER {
+                        res.mut_header().set_cost(COST);
+                    } else {
+                        for hash in polls.remove(0).1 {
+                            let record = res.mut_records().push_default();
+                            record.set_transactionHash(hash.to_vec());
+                            record.mut_transferList();
+                        }
+                    }
+                }
+
+                _ => unimplemented!(),
+            }
+
+            future::ready(Ok(response)).boxed()
+        }
+    }
+
+    fn client(polls: Vec<(u64, Vec<&'static [u8]>)>) -> Result<Client, Error> {
+        let (secret, _) = SecretKey::generate("");
+
+        Client::builder("127.0.0.1:50211")
+            .node(AccountId::new(0, 0, 3))
+            .operator(AccountId::new(0, 0, 1001), move || secret.clone())
+            .transport(Polls(Mutex::new(polls)))
+            .build()
+    }
+
+    fn describe(update: Result<AccountUpdate, Error>) -> String {
+        match update {
+            Ok(AccountUpdate::Balance(balance)) => format!("balance {}", balance),
+            Ok(AccountUpdate::Record(record)) => {
+                format!("record {}", String::from_utf8_lossy(&record.transaction_hash))
+            }
+
+            Err(error) => match error.downcast_ref() {
+                Some(ErrorKind::MaxQueryPaymentExceeded {
+                    query_cost,
+                    max_query_payment,
+                }) => format!("exceeded {} {}", query_cost, max_query_payment),
+
+                _ => format!("error {}", error),
+            },
+        }
+    }
+
+    #[test]
+    fn test_updates() -> Result<(), Error> {
+        let client = client(vec![
+            (100, vec![b"a"]),
+            (100, vec![b"a", b"b"]),
+            (90, vec![b"b", b"c"]),
+        ])?;
+
+        let watcher = client
+            .watch_account(AccountId::new(0, 0, 1002))
+            .interval(Duration::from_millis(1));
+
+        // The watcher can be moved to another task
+        fn assert_send<T: Send>(_: &T) {}
+        assert_send(&watcher);
+
+        let updates = crate::RUNTIME
+            .lock()
+            .block_on(watcher.take(4).map(describe).collect::<Vec<_>>());
+
+        // The records of the first poll already existed
+        assert_eq!(updates, ["balance 100", "record b", "balance 90", "record c"]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_budget() -> Result<(), Error> {
+        let client = client(vec![(100, vec![]), (100, vec![])])?;
+
+        // The first poll costs 20, leaving 5 after the balance query of the second
+        let watcher = client
+            .watch_account(AccountId::new(0, 0, 1002))
+            .interval(Duration::from_millis(1))
+            .budget(3 * COST + 5);
+
+        let updates = crate::RUNTIME
+            .lock()
+            .block_on(watcher.map(describe).collect::<Vec<_>>());
+
+        assert_eq!(updates, ["balance 100", "exceeded 10 5"]);
+
+        Ok(())
+    }
+}
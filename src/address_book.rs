@@ -0,0 +1,176 @@
+use crate::{network::DEFAULT_PORT, proto, AccountId, Endpoint, FileId};
+use failure::{format_err, Error};
+use std::{
+    fs,
+    net::{IpAddr, Ipv4Addr},
+    path::Path,
+};
+use try_from::{TryFrom, TryInto};
+
+/// A node listed in the network address book.
+#[derive(Debug, Clone, PartialEq)]
+pub struct NodeAddress {
+    pub ip_address: String,
+    pub port: u16,
+    pub node: AccountId,
+    pub rsa_public_key: String,
+}
+
+impl NodeAddress {
+    /// The network address of the node.
+    pub fn address(&self) -> Endpoint {
+        Endpoint::new(self.ip_address.clone(), self.port)
+    }
+}
+
+impl TryFrom<proto::BasicTypes::NodeAddress> for NodeAddress {
+    type Err = Error;
+
+    fn try_from(mut address: proto::BasicTypes::NodeAddress) -> Result<Self, Error> {
+        let ip_address = parse_ip_address(&address.take_ipAddress())?;
+
+        // The memo of each node is the ID of its account
+        let memo = String::from_utf8(address.take_memo())?;
+        let node = memo.parse().map_err(|_| {
+            format_err!("node address book has an invalid node account ID: {:?}", memo)
+        })?;
+
+        let port = match address.get_portno() {
+            0 => DEFAULT_PORT,
+            port => port.try_into()?,
+        };
+
+        Ok(Self {
+            ip_address,
+            port,
+            node,
+            rsa_public_key: address.take_RSA_PubKey(),
+        })
+    }
+}
+
+// Some address books store the text of an address, e.g. `10.0.0.1`, and others the 4 octets
+// of an IPv4 address; 4 bytes are only read as octets if they are not a valid address as text
+fn parse_ip_address(bytes: &[u8]) -> Result<String, Error> {
+    if let Ok(text) = std::str::from_utf8(bytes) {
+        let is_host_name = !text.is_empty()
+            && text
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '.');
+
+        if text.parse::<IpAddr>().is_ok() || is_host_name {
+            return Ok(text.to_owned());
+        }
+    }
+
+    match *bytes {
+        [a, b, c, d] => Ok(Ipv4Addr::new(a, b, c, d).to_string()),
+        _ => Err(format_err!("node address book has an invalid IP address: {:?}", bytes)),
+    }
+}
+
+/// The address book of the network, as stored in the address book system file.
+#[derive(Debug, Clone, PartialEq)]
+pub struct NodeAddressBook {
+    pub nodes: Vec<NodeAddress>,
+}
+
+impl NodeAddressBook {
+    /// The system file holding the address book; read it with
+    /// `client.file(NodeAddressBook::FILE_ID).contents()`.
+    pub const FILE_ID: FileId = FileId {
+        shard: 0,
+        realm: 0,
+        file: 101,
+    };
+
+    /// Parse the contents of the address book file.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, Error> {
+        let book: proto::BasicTypes::NodeAddressBook = protobuf::parse_from_bytes(bytes)?;
+
+        book.try_into()
+    }
+
+    /// Load an address book file from disk.
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self, Error> {
+        Self::from_bytes(&fs::read(path)?)
+    }
+}
+
+impl TryFrom<proto::BasicTypes::NodeAddressBook> for NodeAddressBook {
+    type Err = Error;
+
+    fn try_from(mut book: proto::BasicTypes::NodeAddressBook) -> Result<Self, Error> {
+        Ok(Self {
+            nodes: book
+                .take_nodeAddress()
+                .into_iter()
+                .map(TryInto::try_into)
+                .collect::<Result<Vec<_>, _>>()?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::NodeAddress;
+    use crate::{proto, AccountId};
+    use failure::Error;
+    use try_from::TryInto;
+
+    fn node_address(ip_address: &[u8]) -> proto::BasicTypes::NodeAddress {
+        let mut address = proto::BasicTypes::NodeAddress::new();
+        address.set_ipAddress(ip_address.to_vec());
+        address.set_memo(b"0.0.3".to_vec());
+        address.set_RSA_PubKey("308201a2".to_owned());
+        address
+    }
+
+    #[test]
+    fn test_parse_text() -> Result<(), Error> {
+        let address: NodeAddress = node_address(b"34.94.106.61").try_into()?;
+
+        assert_eq!(address.ip_address, "34.94.106.61");
+        assert_eq!(address.port, 50211);
+        assert_eq!(address.node, AccountId::new(0, 0, 3));
+
+        let address: NodeAddress = node_address(b"2001:db8::1").try_into()?;
+        assert_eq!(address.ip_address, "2001:db8::1");
+
+        let address: NodeAddress = node_address(b"node-0.testnet").try_into()?;
+        assert_eq!(address.ip_address, "node-0.testnet");
+
+        // A host name as short as 4 bytes is still text
+        let address: NodeAddress = node_address(b"a.io").try_into()?;
+        assert_eq!(address.ip_address, "a.io");
+
+        let address: NodeAddress = node_address(b"node").try_into()?;
+        assert_eq!(address.ip_address, "node");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_octets() -> Result<(), Error> {
+        // Octets that are not a valid host name or IP address as text
+        let address: NodeAddress = node_address(&[10, 0, 0, 1]).try_into()?;
+        assert_eq!(address.ip_address, "10.0.0.1");
+
+        let address: NodeAddress = node_address(&[34, 94, 106, 61]).try_into()?;
+        assert_eq!(address.ip_address, "34.94.106.61");
+
+        let address: NodeAddress = node_address(&[192, 168, 0, 1]).try_into()?;
+        assert_eq!(address.ip_address, "192.168.0.1");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_invalid() {
+        let address: Result<NodeAddress, _> = node_address(b"").try_into();
+        assert!(address.is_err());
+
+        let address: Result<NodeAddress, _> = node_address(b"10.0.0.1\n;").try_into();
+        assert!(address.is_err());
+    }
+}
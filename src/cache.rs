@@ -0,0 +1,321 @@
+use crate::{
+    proto::{self, TransactionBody::TransactionBody_oneof_data},
+    AccountId, ContractId, FileId, Status, TransactionId,
+};
+use parking_lot::Mutex;
+use std::{
+    collections::HashMap,
+    time::{Duration, Instant},
+};
+
+// How long a transaction accepted by a node is remembered, to be invalidated again when its
+// receipt is seen; the network keeps receipts for 3 minutes
+const PENDING_PERIOD: Duration = Duration::from_secs(180);
+
+/// The kinds of query whose responses can be cached.
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+pub enum CachedQuery {
+    AccountBalance,
+    AccountInfo,
+    FileInfo,
+    ContractInfo,
+}
+
+/// An entity whose cached query responses can be invalidated.
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+pub enum CachedEntity {
+    Account(AccountId),
+    File(FileId),
+    Contract(ContractId),
+}
+
+impl From<AccountId> for CachedEntity {
+    fn from(id: AccountId) -> Self {
+        CachedEntity::Account(id)
+    }
+}
+
+impl From<FileId> for CachedEntity {
+    fn from(id: FileId) -> Self {
+        CachedEntity::File(id)
+    }
+}
+
+impl From<ContractId> for CachedEntity {
+    fn from(id: ContractId) -> Self {
+        CachedEntity::Contract(id)
+    }
+}
+
+/// A cache of query responses, keyed by the kind of query and the entity it is about.
+///
+/// Only the kinds of query given a TTL are cached. Responses are reused until their TTL
+/// has passed, so a cached query is not paid for.
+///
+/// Responses about the entities a transaction changes, and about the account paying its
+/// fee, are invalidated when a node accepts the transaction, and again when the client
+/// receives its receipt or record, as responses received in between can predate it.
+#[derive(Default)]
+pub struct QueryCache {
+    ttls: HashMap<CachedQuery, Duration>,
+    entries: Mutex<HashMap<(CachedQuery, CachedEntity), (Instant, proto::Response::Response)>>,
+    // Transactions accepted by a node whose receipt has not been seen, with when they were
+    pending: Mutex<Vec<(Instant, TransactionId, TransactionBody_oneof_data)>>,
+}
+
+impl QueryCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Cache responses to the given kind of query for `ttl`.
+    pub fn ttl(mut self, query: CachedQuery, ttl: Duration) -> Self {
+        self.ttls.insert(query, ttl);
+        self
+    }
+
+    /// Remove every cached response about the entity.
+    pub fn invalidate(&self, entity: impl Into<CachedEntity>) {
+        let entity = entity.into();
+
+        self.entries.lock().retain(|(_, key), _| *key != entity);
+    }
+
+    /// Remove every cached response.
+    pub fn clear(&self) {
+        self.entries.lock().clear();
+    }
+
+    pub(crate) fn get(
+        &self,
+        query: CachedQuery,
+        entity: CachedEntity,
+    ) -> Option<proto::Response::Response> {
+        let ttl = self.ttls.get(&query)?;
+        let mut entries = self.entries.lock();
+
+        match entries.get(&(query, entity)) {
+            Some((inserted, response)) if inserted.elapsed() < *ttl => Some(response.clone()),
+
+            Some(_) => {
+                entries.remove(&(query, entity));
+                None
+            }
+
+            None => None,
+        }
+    }
+
+    pub(crate) fn insert(
+        &self,
+        query: CachedQuery,
+        entity: CachedEntity,
+        response: &proto::Response::Response,
+    ) {
+        if self.ttls.contains_key(&query) {
+            self.entries
+                .lock()
+                .insert((query, entity), (Instant::now(), response.clone()));
+        }
+    }
+
+    // Invalidate what a transaction accepted by a node changes, and the balance of its payer,
+    // and remember the transaction until its receipt is seen
+    pub(crate) fn submitted(&self, id: &TransactionId, data: TransactionBody_oneof_data) {
+        self.invalidate_for(&data);
+        self.invalidate(id.account_id);
+
+        let now = Instant::now();
+        let mut pending = self.pending.lock();
+
+        pending.retain(|(submitted, _, _)| now.duration_since(*submitted) < PENDING_PERIOD);
+        pending.push((now, id.clone(), data));
+    }
+
+    // Look for the receipt of a pending transaction in the response to a query
+    pub(crate) fn observe(
+        &self,
+        query: &proto::Query::Query,
+        response: &proto::Response::Response,
+    ) {
+        use self::proto::{Query::Query_oneof_query, Response::Response_oneof_response};
+
+        let id = match &query.query {
+            Some(Query_oneof_query::transactionGetReceipt(q)) => q.get_transactionID(),
+            Some(Query_oneof_query::transactionGetRecord(q)) => q.get_transactionID(),
+            Some(Query_oneof_query::transactionGetFastRecord(q)) => q.get_transactionID(),
+
+            _ => return,
+        };
+
+        let receipt = match &response.response {
+            Some(Response_oneof_response::transactionGetReceipt(res)) => res.get_receipt(),
+
+            Some(Response_oneof_response::transactionGetRecord(res)) => {
+                res.get_transactionRecord().get_receipt()
+            }
+
+            Some(Response_oneof_response::transactionGetFastRecord(res)) => {
+                res.get_transactionRecord().get_receipt()
+            }
+
+            _ => return,
+        };
+
+        self.settled(&id.clone().into(), receipt.get_status().into());
+    }
+
+    // Invalidate again what a pending transaction changes once it has reached consensus; its
+    // payer is charged a fee even when it failed
+    fn settled(&self, id: &TransactionId, status: Status) {
+        if status == Status::Unknown {
+            return;
+        }
+
+        let data = {
+            let mut pending = self.pending.lock();
+
+            match pending.iter().position(|(_, pending, _)| pending == id) {
+                Some(index) => pending.remove(index).2,
+                None => return,
+            }
+        };
+
+        self.invalidate(id.account_id);
+
+        if status == Status::Success {
+            self.invalidate_for(&data);
+        }
+    }
+
+    // Invalidate the entities that a transaction changes
+    fn invalidate_for(&self, data: &TransactionBody_oneof_data) {
+        use self::TransactionBody_oneof_data::*;
+
+        match data {
+            cryptoUpdateAccount(data) => {
+                self.invalidate(AccountId::from(data.get_accountIDToUpdate().clone()))
+            }
+
+            cryptoDelete(data) => {
+                self.invalidate(AccountId::from(data.get_deleteAccountID().clone()));
+                self.invalidate(AccountId::from(data.get_transferAccountID().clone()));
+            }
+
+            cryptoTransfer(data) => {
+                for transfer in data.get_transfers().get_accountAmounts() {
+                    self.invalidate(AccountId::from(transfer.get_accountID().clone()));
+                }
+            }
+
+            fileUpdate(data) => self.invalidate(FileId::from(data.get_fileID().clone())),
+            fileAppend(data) => self.invalidate(FileId::from(data.get_fileID().clone())),
+            fileDelete(data) => self.invalidate(FileId::from(data.get_fileID().clone())),
+
+            contractUpdateInstance(data) => {
+                self.invalidate(ContractId::from(data.get_contractID().clone()))
+            }
+
+            contractDeleteInstance(data) => {
+                self.invalidate(ContractId::from(data.get_contractID().clone()))
+            }
+
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{CachedEntity, CachedQuery, QueryCache};
+    use crate::{
+        proto::{
+            self, ResponseCode::ResponseCodeEnum, ToProto,
+            TransactionBody::TransactionBody_oneof_data,
+        },
+        AccountId, FileId, TransactionId,
+    };
+    use failure::Error;
+    use std::time::Duration;
+
+    #[test]
+    fn test_ttl() {
+        let cache = QueryCache::new()
+            .ttl(CachedQuery::AccountInfo, Duration::from_secs(60))
+            .ttl(CachedQuery::FileInfo, Duration::from_secs(0));
+
+        let account = CachedEntity::Account(AccountId::new(0, 0, 1001));
+        let file = CachedEntity::File(FileId::new(0, 0, 1002));
+        let response = proto::Response::Response::new();
+
+        cache.insert(CachedQuery::AccountInfo, account, &response);
+        cache.insert(CachedQuery::AccountBalance, account, &response);
+        cache.insert(CachedQuery::FileInfo, file, &response);
+
+        assert!(cache.get(CachedQuery::AccountInfo, account).is_some());
+        assert!(cache.get(CachedQuery::AccountBalance, account).is_none());
+        assert!(cache.get(CachedQuery::FileInfo, file).is_none());
+    }
+
+    #[test]
+    fn test_invalidate() {
+        let cache = QueryCache::new().ttl(CachedQuery::AccountInfo, Duration::from_secs(60));
+
+        let account1 = AccountId::new(0, 0, 1001);
+        let account2 = AccountId::new(0, 0, 1002);
+        let response = proto::Response::Response::new();
+
+        cache.insert(CachedQuery::AccountInfo, account1.into(), &response);
+        cache.insert(CachedQuery::AccountInfo, account2.into(), &response);
+        cache.invalidate(account1);
+
+        assert!(cache.get(CachedQuery::AccountInfo, account1.into()).is_none());
+        assert!(cache.get(CachedQuery::AccountInfo, account2.into()).is_some());
+    }
+
+    #[test]
+    fn test_invalidate_on_receipt() -> Result<(), Error> {
+        let cache = QueryCache::new().ttl(CachedQuery::AccountBalance, Duration::from_secs(60));
+
+        let payer = AccountId::new(0, 0, 1001);
+        let receiver = AccountId::new(0, 0, 1002);
+        let id = TransactionId::new(payer);
+        let response = proto::Response::Response::new();
+
+        let mut transfer = proto::CryptoTransfer::CryptoTransferTransactionBody::new();
+        let mut amount = proto::CryptoTransfer::AccountAmount::new();
+        amount.set_accountID(receiver.to_proto()?);
+        amount.set_amount(500);
+        transfer.mut_transfers().mut_accountAmounts().push(amount);
+
+        cache.submitted(&id, TransactionBody_oneof_data::cryptoTransfer(transfer));
+
+        // Balances read before the transfer reached consensus
+        cache.insert(CachedQuery::AccountBalance, payer.into(), &response);
+        cache.insert(CachedQuery::AccountBalance, receiver.into(), &response);
+
+        let mut query = proto::Query::Query::new();
+        query.mut_transactionGetReceipt().set_transactionID(id.to_proto()?);
+
+        let mut receipt = proto::Response::Response::new();
+        receipt
+            .mut_transactionGetReceipt()
+            .mut_receipt()
+            .set_status(ResponseCodeEnum::UNKNOWN);
+
+        // Not yet final
+        cache.observe(&query, &receipt);
+        assert!(cache.get(CachedQuery::AccountBalance, receiver.into()).is_some());
+
+        receipt
+            .mut_transactionGetReceipt()
+            .mut_receipt()
+            .set_status(ResponseCodeEnum::SUCCESS);
+
+        cache.observe(&query, &receipt);
+        assert!(cache.get(CachedQuery::AccountBalance, payer.into()).is_none());
+        assert!(cache.get(CachedQuery::AccountBalance, receiver.into()).is_none());
+
+        Ok(())
+    }
+}
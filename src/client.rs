@@ -1,14 +1,23 @@
 use crate::{
-    crypto::SecretKey,
+    account_watcher::AccountWatcher,
+    address_book::NodeAddressBook,
+    cache::QueryCache,
+    config::Config,
+    fixture::{Recorder, Replay},
+    health::NodeHealth,
+    interceptor::{Interceptor, Interceptors},
+    crypto::{PublicKey, SecretKey},
     id::{ContractId, FileId},
-    proto::{
-        CryptoService_grpc::CryptoServiceClient, FileService_grpc::FileServiceClient,
-        SmartContractService_grpc::SmartContractServiceClient,
-    },
+    network::{Endpoint, Network},
+    rate_limit::RateLimiter,
+    retry::RetryPolicy,
+    tls::{self, TlsConfig},
+    transport::{GrpcTransport, Transport},
     query::{
-        Query, QueryCryptoGetAccountBalance, QueryCryptoGetClaim, QueryCryptoGetInfo,
-        QueryFileGetContents, QueryFileGetInfo, QueryTransactionGetReceipt,
-        QueryTransactionGetRecord,
+        self, Query, QueryContractGetRecords, QueryCryptoGetAccountBalance, QueryCryptoGetClaim,
+        QueryCryptoGetInfo, QueryCryptoGetStakers, QueryFileGetContents, QueryFileGetInfo,
+        QueryGetByKey, QueryGetBySolidityId, QueryTransactionGetFastRecord,
+        QueryTransactionGetReceipt, QueryTransactionGetRecord,
     },
     transaction::{
         Transaction, TransactionContractCall, TransactionContractCreate, TransactionContractUpdate,
@@ -16,31 +25,143 @@ use crate::{
         TransactionCryptoDeleteClaim, TransactionCryptoTransfer, TransactionCryptoUpdate,
         TransactionFileAppend, TransactionFileCreate, TransactionFileDelete,
     },
-    AccountId, TransactionId,
+    AccountId, TransactionId, TransactionReceipt,
 };
-use failure::{err_msg, format_err, Error};
-use grpc::ClientStub;
-use itertools::Itertools;
-use std::{fmt, sync::Arc, time::Duration};
+#[cfg(feature = "metrics")]
+use crate::metrics::{Metrics, MetricsRecorder};
+use failure::{err_msg, format_err, Error, ResultExt};
+use futures::Stream;
+use std::{collections::HashMap, env, fmt, path::Path, sync::Arc, time::Duration};
 use try_from::TryInto;
 
-pub struct ClientBuilder<'a> {
-    address: &'a str,
+pub struct ClientBuilder {
+    address: Option<Endpoint>,
+    network: HashMap<Endpoint, AccountId>,
+    node_keys: HashMap<AccountId, String>,
     node: Option<AccountId>,
     operator: Option<AccountId>,
     operator_secret: Option<Arc<dyn Fn() -> Result<SecretKey, Error> + Send + Sync>>,
+    max_query_payment: Option<u64>,
+    request_timeout: Option<Duration>,
+    retry: RetryPolicy,
+    default_fee: u64,
+    cache: Option<QueryCache>,
+    rate_limiter: Option<RateLimiter>,
+    interceptors: Vec<Box<dyn Interceptor>>,
+    transport: Option<Arc<dyn Transport>>,
+    offline: bool,
+    connection_timeout: Duration,
+    no_delay: bool,
+    tls: Option<TlsConfig>,
+    // The first address that could not be parsed, reported by `build`
+    error: Option<Error>,
 }
 
 pub struct Client {
     pub(crate) node: Option<AccountId>,
     pub(crate) operator: Option<AccountId>,
     pub(crate) operator_secret: Option<Arc<dyn Fn() -> Result<SecretKey, Error> + Send + Sync>>,
-    pub(crate) crypto: Arc<CryptoServiceClient>,
-    pub(crate) file: Arc<FileServiceClient>,
-    pub(crate) contract: Arc<SmartContractServiceClient>,
+    pub(crate) max_query_payment: Option<u64>,
+    pub(crate) request_timeout: Option<Duration>,
+    pub(crate) retry: RetryPolicy,
+    pub(crate) default_fee: u64,
+    pub(crate) cache: Option<Arc<QueryCache>>,
+    pub(crate) rate_limiter: Option<Arc<RateLimiter>>,
+    pub(crate) interceptors: Arc<Interceptors>,
+    pub(crate) network: Arc<Network>,
 }
 
-impl<'a> ClientBuilder<'a> {
+impl ClientBuilder {
+    fn new() -> Self {
+        Self {
+            address: None,
+            network: HashMap::new(),
+            node_keys: HashMap::new(),
+            node: None,
+            operator: None,
+            operator_secret: None,
+            max_query_payment: None,
+            request_timeout: None,
+            retry: RetryPolicy::default(),
+            default_fee: 100_300_000,
+            cache: None,
+            rate_limiter: None,
+            interceptors: Vec::new(),
+            transport: None,
+            offline: false,
+            connection_timeout: Duration::from_secs(5),
+            no_delay: true,
+            tls: None,
+            error: None,
+        }
+    }
+
+    /// Start building a client for every node listed in an address book.
+    ///
+    /// The address book can be read from any one node, as the contents of
+    /// `NodeAddressBook::FILE_ID`, or loaded from disk.
+    ///
+    /// The public key listed for each node is kept, to be used by `pin_certificates`.
+    pub fn from_address_book(book: &NodeAddressBook) -> Self {
+        let mut builder =
+            Self::new().network(book.nodes.iter().map(|node| (node.address(), node.node)));
+
+        builder.node_keys = book
+            .nodes
+            .iter()
+            .map(|node| (node.node, node.rsa_public_key.clone()))
+            .collect();
+
+        builder
+    }
+
+    /// Start building a client from a configuration file, in JSON or TOML.
+    ///
+    /// The file lists the nodes of the network, or names a preset, along with the operator
+    /// and where to find its key, the default fee, the retry policy and timeouts. A key file
+    /// given as a relative path is found relative to the configuration file.
+    pub fn from_config(path: impl AsRef<Path>) -> Result<Self, Error> {
+        Config::load(path.as_ref())?.into_builder()
+    }
+
+    /// Start building a client from the configuration file named by the `HEDERA_CONFIG`
+    /// environment variable. See `from_config`.
+    pub fn from_env() -> Result<Self, Error> {
+        let path = env::var_os("HEDERA_CONFIG")
+            .ok_or_else(|| err_msg("the HEDERA_CONFIG environment variable is not set"))?;
+
+        Self::from_config(path)
+    }
+
+    /// Add nodes to the network the client sends requests to, as a map from the address
+    /// of each node to its account. Takes a `NetworkPreset`, or addresses as strings,
+    /// `SocketAddr`s, `(host, port)` pairs or `Endpoint`s.
+    ///
+    /// Unless a node is set, each query and transaction is sent to the next node in turn.
+    pub fn network<E>(mut self, network: impl IntoIterator<Item = (E, AccountId)>) -> Self
+    where
+        E: TryInto<Endpoint, Err = Error>,
+    {
+        for (address, node) in network {
+            match address.try_into() {
+                Ok(address) => {
+                    self.network.insert(address, node);
+                }
+
+                Err(error) => {
+                    self.error.get_or_insert(error);
+                }
+            }
+        }
+
+        self
+    }
+
+    /// Send every query and transaction to this node, unless another is set on the request.
+    ///
+    /// A node set here is used even while it is failing: requests are only moved to a
+    /// healthy node when the client picks the node for each one, which needs a `network`
+    /// and no node set. A client for a single address always sends to that node.
     pub fn node(mut self, node: AccountId) -> Self {
         self.node = Some(node);
         self
@@ -61,77 +182,338 @@ impl<'a> ClientBuilder<'a> {
         self
     }
 
-    pub fn build(self) -> Result<Client, Error> {
-        let mut client = Client::new(&self.address)?;
-
-        if let Some(node) = self.node {
-            client.set_node(node);
+    /// Sign as the operator with the key in a keystore file written by
+    /// `SecretKey::to_encrypted`.
+    ///
+    /// The password is asked for once, such as from an environment variable or a prompt,
+    /// and the key is decrypted when the client is built, not on each transaction.
+    pub fn operator_keystore<E>(
+        self,
+        operator: AccountId,
+        path: impl AsRef<Path>,
+        password: impl FnOnce() -> Result<String, E>,
+    ) -> Self
+    where
+        E: fmt::Debug + fmt::Display + Send + Sync + 'static,
+    {
+        let secret = password()
+            .map_err(err_msg)
+            .and_then(|password| SecretKey::from_keystore_file(path, &password));
+
+        match secret {
+            Ok(secret) => self.operator(operator, move || secret.clone()),
+
+            Err(error) => {
+                let mut builder = self;
+                builder.error.get_or_insert(error);
+                builder
+            }
         }
+    }
 
-        if let (Some(operator), Some(secret)) = (self.operator, self.operator_secret) {
-            client.operator = Some(operator);
-            client.operator_secret = Some(secret);
+    /// The maximum amount, in tinybars, that will be paid automatically for a query.
+    pub fn max_query_payment(mut self, max_query_payment: u64) -> Self {
+        self.max_query_payment = Some(max_query_payment);
+        self
+    }
+
+    /// How long to wait for a node to respond to a query or transaction.
+    pub fn request_timeout(mut self, timeout: Duration) -> Self {
+        self.request_timeout = Some(timeout);
+        self
+    }
+
+    /// How queries and transactions are retried when the node is busy.
+    pub fn retry(mut self, retry: RetryPolicy) -> Self {
+        self.retry = retry;
+        self
+    }
+
+    /// The fee, in tinybars, of each transaction unless another is set on the transaction.
+    pub fn default_fee(mut self, fee: u64) -> Self {
+        self.default_fee = fee;
+        self
+    }
+
+    /// Cache responses to info and balance queries.
+    pub fn cache(mut self, cache: QueryCache) -> Self {
+        self.cache = Some(cache);
+        self
+    }
+
+    /// Hold back requests to stay under the given rates. Queries and transactions wait for
+    /// capacity, except when sent with `try_get` or `try_execute`, which fail with
+    /// `ErrorKind::RateLimited` instead.
+    pub fn rate_limiter(mut self, limiter: RateLimiter) -> Self {
+        self.rate_limiter = Some(limiter);
+        self
+    }
+
+    /// Register an interceptor, which sees every request sent to a node and every response,
+    /// and can veto or annotate requests.
+    pub fn interceptor(mut self, interceptor: impl Interceptor + 'static) -> Self {
+        self.interceptors.push(Box::new(interceptor));
+        self
+    }
+
+    /// Record metrics of the transactions and queries sent by the client.
+    #[cfg(feature = "metrics")]
+    pub fn metrics(self, recorder: impl MetricsRecorder + 'static) -> Self {
+        self.interceptor(Metrics(recorder))
+    }
+
+    /// Write every request sent to a node, and the response to it, to a fixture file that
+    /// can be replayed with `replay`. The file is replaced if it exists.
+    pub fn record(mut self, path: impl AsRef<Path>) -> Self {
+        match Recorder::create(path.as_ref()) {
+            Ok(recorder) => self.interceptors.push(Box::new(recorder)),
+
+            Err(error) => {
+                self.error.get_or_insert(error);
+            }
         }
 
-        Ok(client)
+        self
     }
-}
 
-impl Client {
-    pub fn builder(address: &str) -> ClientBuilder {
-        ClientBuilder {
-            address,
-            node: None,
-            operator: None,
-            operator_secret: None,
+    /// Answer every request with the responses in a fixture file written by `record`,
+    /// instead of connecting to the network.
+    ///
+    /// A request is answered with the responses to the recorded request of the same kind
+    /// about the same account, file, contract or, for receipts and records, payer; its
+    /// signatures, payment and timestamps are not compared. Responses are replayed in the
+    /// order they were recorded, repeating the last. A request that matches nothing fails.
+    pub fn replay(mut self, path: impl AsRef<Path>) -> Self {
+        match Replay::load(path.as_ref()) {
+            Ok(replay) => self.transport = Some(Arc::new(replay)),
+
+            Err(error) => {
+                self.error.get_or_insert(error);
+            }
         }
+
+        self
     }
 
-    pub fn new(address: impl AsRef<str>) -> Result<Self, Error> {
-        let address = address.as_ref();
-        let (host, port) = address.split(':').next_tuple().ok_or_else(|| {
-            format_err!("failed to parse 'host:port' from address: {:?}", address)
-        })?;
-
-        let port = port.parse()?;
-
-        let inner = Arc::new(grpc::Client::new_plain(
-            &host,
-            port,
-            grpc::ClientConf {
-                http: httpbis::ClientConf {
-                    no_delay: Some(true),
-                    connection_timeout: Some(Duration::from_secs(5)),
-                    ..httpbis::ClientConf::default()
-                },
-            },
-        )?);
-
-        let crypto = Arc::new(CryptoServiceClient::with_client(inner.clone()));
-        let file = Arc::new(FileServiceClient::with_client(inner.clone()));
-        let contract = Arc::new(SmartContractServiceClient::with_client(inner.clone()));
-
-        // Default the node to what we know every testnet is on
-        let node = if address.starts_with("testnet.") {
-            Some(AccountId {
-                shard: 0,
-                realm: 0,
-                account: 3,
-            })
+    /// Send every request through `transport` instead of connecting to each node over gRPC.
+    ///
+    /// The addresses of nodes are not used, and neither are the connection and TLS settings.
+    pub fn transport(mut self, transport: impl Transport + 'static) -> Self {
+        self.transport = Some(Arc::new(transport));
+        self
+    }
+
+    /// Never connect to a node, for signing transactions on a machine without a network.
+    ///
+    /// Transactions can still be built, signed and serialized with `Transaction::to_bytes`,
+    /// for the nodes given to `network` or `node`, but executing one, or any query, fails
+    /// with `ErrorKind::Offline`.
+    pub fn offline(mut self) -> Self {
+        self.offline = true;
+        self
+    }
+
+    /// How long to wait for the connection to the node to be established. Defaults to 5 seconds.
+    pub fn connection_timeout(mut self, timeout: Duration) -> Self {
+        self.connection_timeout = timeout;
+        self
+    }
+
+    /// Set `TCP_NODELAY` on the connection to the node. Defaults to `true`.
+    pub fn no_delay(mut self, no_delay: bool) -> Self {
+        self.no_delay = no_delay;
+        self
+    }
+
+    /// Connect to nodes over TLS, verifying their certificates against the default roots.
+    ///
+    /// Connections are plaintext unless this is set, or the address of a node is given as
+    /// an `https://` URI.
+    pub fn tls(mut self) -> Self {
+        self.tls.get_or_insert_with(TlsConfig::default);
+        self
+    }
+
+    /// Trust a DER-encoded root certificate, in addition to the default roots, when verifying
+    /// the certificate of a node. Implies `tls`.
+    pub fn add_root_certificate(mut self, certificate: impl Into<Vec<u8>>) -> Self {
+        self.tls
+            .get_or_insert_with(TlsConfig::default)
+            .roots
+            .push(certificate.into());
+
+        self
+    }
+
+    /// Accept a node only if its certificate is for the RSA public key listed for it in the
+    /// address book given to `from_address_book`. Implies `tls`.
+    pub fn pin_certificates(mut self) -> Self {
+        self.tls.get_or_insert_with(TlsConfig::default).pin = true;
+        self
+    }
+
+    pub fn build(self) -> Result<Client, Error> {
+        if let Some(error) = self.error {
+            return Err(error);
+        }
+
+        let network = if self.offline {
+            Network::offline(self.network.values().cloned().collect())
         } else {
-            None
+            self.connect()?
+        };
+
+        // Default the node to what we know every testnet is on, for a client of that one
+        // node; with a network, the node is picked for each request instead
+        let node = match &self.address {
+            Some(address)
+                if self.node.is_none()
+                    && self.network.is_empty()
+                    && address.host.starts_with("testnet.") =>
+            {
+                Some(AccountId {
+                    shard: 0,
+                    realm: 0,
+                    account: 3,
+                })
+            }
+
+            _ => self.node,
         };
 
-        Ok(Self {
+        let (operator, operator_secret) = match (self.operator, self.operator_secret) {
+            (Some(operator), Some(secret)) => (Some(operator), Some(secret)),
+            _ => (None, None),
+        };
+
+        Ok(Client {
             node,
-            operator: None,
-            operator_secret: None,
-            crypto,
-            file,
-            contract,
+            operator,
+            operator_secret,
+            max_query_payment: self.max_query_payment,
+            request_timeout: self.request_timeout,
+            retry: self.retry,
+            default_fee: self.default_fee,
+            cache: self.cache.map(Arc::new),
+            rate_limiter: self.rate_limiter.map(Arc::new),
+            interceptors: Arc::new(Interceptors(self.interceptors)),
+            network: Arc::new(network),
         })
     }
 
+    // Open a transport to every node
+    fn connect(&self) -> Result<Network, Error> {
+        let http = httpbis::ClientConf {
+            no_delay: Some(self.no_delay),
+            connection_timeout: Some(self.connection_timeout),
+            ..httpbis::ClientConf::default()
+        };
+
+        let pin = self.tls.as_ref().map_or(false, |tls| tls.pin);
+
+        // An `https` address is connected to over TLS even when the client is not
+        let default_tls = TlsConfig::default();
+        let tls = |address: &Endpoint| match &self.tls {
+            Some(tls) => Some(tls),
+            None if address.tls => Some(&default_tls),
+            None => None,
+        };
+
+        let mut transports: HashMap<_, Arc<dyn Transport>> = HashMap::new();
+
+        let fallback: Option<Arc<dyn Transport>> = if let Some(transport) = &self.transport {
+            for node in self.network.values() {
+                transports.insert(*node, transport.clone());
+            }
+
+            Some(transport.clone())
+        } else {
+            for (address, node) in &self.network {
+                let pinned_key = if pin {
+                    let key = self.node_keys.get(node).ok_or_else(|| {
+                        format_err!(
+                            "no public key is known to pin the certificate of node {}",
+                            node
+                        )
+                    })?;
+
+                    Some(tls::pinned_key(key).with_context(|_| {
+                        format!("invalid public key to pin the certificate of node {}", node)
+                    })?)
+                } else {
+                    None
+                };
+
+                let pinned_key = pinned_key.as_ref().map(Vec::as_slice);
+                let tls = tls(address);
+                let transport = GrpcTransport::connect(address, http.clone(), tls, pinned_key)?;
+                transports.insert(*node, Arc::new(transport));
+            }
+
+            match &self.address {
+                Some(_) if pin => Err(format_err!(
+                    "cannot pin the certificate of a node that is not in an address book"
+                ))?,
+
+                Some(address) => {
+                    let transport = GrpcTransport::connect(address, http, tls(address), None)?;
+                    Some(Arc::new(transport))
+                }
+
+                None => None,
+            }
+        };
+
+        Ok(Network::new(transports, fallback))
+    }
+}
+
+impl Client {
+    /// Start building a client for a single node, given its address as a string,
+    /// `SocketAddr`, `(host, port)` pair or `Endpoint`.
+    pub fn builder<E>(address: E) -> ClientBuilder
+    where
+        E: TryInto<Endpoint, Err = Error>,
+    {
+        let mut builder = ClientBuilder::new();
+
+        match address.try_into() {
+            Ok(address) => builder.address = Some(address),
+            Err(error) => builder.error = Some(error),
+        }
+
+        builder
+    }
+
+    /// Start building a client for a network of nodes, such as `NetworkPreset::Testnet`, or
+    /// a map from the address of each node to its account.
+    pub fn builder_for_network<E>(
+        network: impl IntoIterator<Item = (E, AccountId)>,
+    ) -> ClientBuilder
+    where
+        E: TryInto<Endpoint, Err = Error>,
+    {
+        ClientBuilder::new().network(network)
+    }
+
+    /// Start building a client that never connects to a node; see `ClientBuilder::offline`.
+    pub fn offline() -> ClientBuilder {
+        ClientBuilder::new().offline()
+    }
+
+    /// Connect to a single node, given its address.
+    ///
+    /// Every request is sent to that node, whether or not it is healthy; for an address on
+    /// the testnet, the account of the node defaults to `0.0.3`. To skip failing nodes, use
+    /// `builder_for_network` with more than one node.
+    pub fn new(address: impl AsRef<str>) -> Result<Self, Error> {
+        Self::builder(address.as_ref()).build()
+    }
+
+    /// Send every query and transaction to this node, unless another is set on the request.
+    ///
+    /// As with `ClientBuilder::node`, the node is used even while it is failing, instead of
+    /// the next healthy node of the network.
     #[inline]
     pub fn set_node(&mut self, node: AccountId) {
         self.node = Some(node);
@@ -150,6 +532,53 @@ impl Client {
         self.operator_secret = Some(Arc::new(move || secret().try_into().map_err(err_msg)));
     }
 
+    /// Set the maximum amount, in tinybars, that will be paid automatically for a query.
+    ///
+    /// When set, the cost of each query is looked up before paying for it and the query
+    /// fails with `ErrorKind::MaxQueryPaymentExceeded` if the cost is above this amount.
+    #[inline]
+    pub fn set_max_query_payment(&mut self, max_query_payment: u64) {
+        self.max_query_payment = Some(max_query_payment);
+    }
+
+    /// Set how long to wait for a node to respond to a query or transaction before failing
+    /// with `ErrorKind::Timeout`. Can be overridden for each query or transaction.
+    #[inline]
+    pub fn set_request_timeout(&mut self, timeout: Duration) {
+        self.request_timeout = Some(timeout);
+    }
+
+    /// Set how queries and transactions are retried when the node is busy.
+    #[inline]
+    pub fn set_retry(&mut self, retry: RetryPolicy) {
+        self.retry = retry;
+    }
+
+    /// Set the fee, in tinybars, of each transaction unless another is set on the transaction.
+    #[inline]
+    pub fn set_default_fee(&mut self, fee: u64) {
+        self.default_fee = fee;
+    }
+
+    /// The health of each node in the network, as seen by this client.
+    ///
+    /// A node that is busy, not active, or cannot be reached is skipped when picking a node
+    /// for new queries and transactions, for a time that grows with each failure in a row.
+    /// Nodes are only picked for requests without a node set, on the request or the client.
+    pub fn node_health(&self) -> Vec<NodeHealth> {
+        self.network.health()
+    }
+
+    /// The cache of query responses, if one was configured.
+    ///
+    /// Entries are invalidated automatically when a transaction changing the entity passes
+    /// pre-check, and again when its receipt or record is received; use this to invalidate
+    /// them explicitly.
+    #[inline]
+    pub fn cache(&self) -> Option<&QueryCache> {
+        self.cache.as_ref().map(|cache| &**cache)
+    }
+
     #[inline]
     pub fn transfer_crypto(&self) -> Transaction<TransactionCryptoTransfer> {
         TransactionCryptoTransfer::new(self)
@@ -174,6 +603,15 @@ impl Client {
         PartialAccountMessage(self, id)
     }
 
+    /// Watch an account for balance changes and new transaction records.
+    ///
+    /// Every poll runs a balance query and an account records query, which are paid for
+    /// by the operator at their cost; see `AccountWatcher`.
+    #[inline]
+    pub fn watch_account(&self, id: AccountId) -> AccountWatcher<'_> {
+        AccountWatcher::new(self, id)
+    }
+
     /// Start a new smart contract instance.
     #[inline]
     pub fn create_contract(&self) -> Transaction<TransactionContractCreate> {
@@ -217,6 +655,26 @@ impl Client {
         PartialFileMessage(self, id)
     }
 
+    /// Get all accounts, claims, files, and smart contract instances whose associated keys
+    /// include the given key.
+    ///
+    /// No node service currently answers this query; it fails with
+    /// `ErrorKind::UnsupportedQuery` until one does.
+    #[inline]
+    pub fn entities_for_key(&self, key: PublicKey) -> Query<QueryGetByKey> {
+        QueryGetByKey::new(self, key)
+    }
+
+    /// Get the account, file, or smart contract instance IDs for an address in the format
+    /// used by Solidity (40 hex characters, as accepted by `CallParams::add_address_string`).
+    #[inline]
+    pub fn resolve_solidity_address(
+        &self,
+        address: impl Into<String>,
+    ) -> Query<QueryGetBySolidityId> {
+        QueryGetBySolidityId::new(self, address.into())
+    }
+
     #[inline]
     pub fn transaction(&self, id: TransactionId) -> PartialTransactionMessage {
         PartialTransactionMessage(self, id)
@@ -238,6 +696,13 @@ impl<'a> PartialAccountMessage<'a> {
         QueryCryptoGetInfo::new(self.0, self.1)
     }
 
+    /// Get all the accounts that are proxy staking to this account, and the number of
+    /// tinybars each is currently proxy staking.
+    #[inline]
+    pub fn stakers(self) -> Query<QueryCryptoGetStakers> {
+        QueryCryptoGetStakers::new(self.0, self.1)
+    }
+
     /// Change properties for the given account. Any missing field is ignored (left unchanged).
     /// This transaction must be signed by the existing key for this account.
     #[inline]
@@ -310,6 +775,13 @@ impl<'a> PartialContractMessage<'a> {
     pub fn update(self) -> Transaction<TransactionContractUpdate> {
         TransactionContractUpdate::new(self.0, self.1)
     }
+
+    /// Get all the records for a smart contract instance, for any function call
+    /// (or the constructor call) during the last 25 hours, for which a Record was requested.
+    #[inline]
+    pub fn records(self) -> Query<QueryContractGetRecords> {
+        QueryContractGetRecords::new(self.0, self.1)
+    }
 }
 
 pub struct PartialTransactionMessage<'a>(&'a Client, TransactionId);
@@ -324,6 +796,18 @@ impl<'a> PartialTransactionMessage<'a> {
         QueryTransactionGetReceipt::new(self.0, self.1)
     }
 
+    /// Watch the receipt of a transaction as it reaches consensus.
+    ///
+    /// Yields the receipt each time its status changes and ends after the final status. The
+    /// first receipt has `Status::Unknown` while the transaction is waiting for consensus; a
+    /// transaction that has already reached it yields only its final receipt. Fails with
+    /// `ErrorKind::ReceiptTimeout` if the transaction has no final status once the receipt
+    /// period has passed since its valid start. Drop the stream to stop polling.
+    #[inline]
+    pub fn receipt_updates(self) -> impl Stream<Item = Result<TransactionReceipt, Error>> {
+        query::receipt_updates(self.0, self.1)
+    }
+
     /// Get the record for a transaction.
     ///
     /// If the transaction requested a record, then the record lasts for one hour, and a state
@@ -332,4 +816,102 @@ impl<'a> PartialTransactionMessage<'a> {
     pub fn record(self) -> Query<QueryTransactionGetRecord> {
         QueryTransactionGetRecord::new(self.0, self.1)
     }
+
+    /// Get the record for a transaction while it is still fresh.
+    ///
+    /// Fast records last for 180 seconds, like receipts, and are free to request.
+    #[inline]
+    pub fn fast_record(self) -> Query<QueryTransactionGetFastRecord> {
+        QueryTransactionGetFastRecord::new(self.0, self.1)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{proto, AccountId, Client, ErrorKind, SecretKey};
+    use failure::Error;
+
+    #[test]
+    fn test_offline() -> Result<(), Error> {
+        let (secret, _) = SecretKey::generate("");
+        let operator = AccountId::new(0, 0, 1001);
+        let node = AccountId::new(0, 0, 3);
+
+        let client = Client::offline()
+            .node(node)
+            .operator(operator, move || secret.clone())
+            .build()?;
+
+        let mut tx = client.transfer_crypto();
+        tx.transfer(operator, -500).transfer(AccountId::new(0, 0, 1002), 500);
+
+        let bytes = tx.to_bytes()?;
+        let decoded: proto::Transaction::Transaction = protobuf::parse_from_bytes(&bytes)?;
+
+        assert_eq!(AccountId::from(decoded.get_body().get_nodeAccountID().clone()), node);
+        // Signed once as the operator and once as the sender
+        assert_eq!(decoded.get_sigs().get_sigs().len(), 2);
+        assert!(tx.describe()?.contains("transactionFee"));
+
+        let error = tx.execute().unwrap_err();
+        match error.downcast_ref::<ErrorKind>() {
+            Some(ErrorKind::Offline) => {}
+            _ => panic!("expected the client to be offline, got: {}", error),
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_node_selection() -> Result<(), Error> {
+        let (secret, _) = SecretKey::generate("");
+        let operator = AccountId::new(0, 0, 1001);
+        let (node3, node4) = (AccountId::new(0, 0, 3), AccountId::new(0, 0, 4));
+
+        let mut client = Client::builder_for_network(vec![
+            ("10.0.0.3:50211", node3),
+            ("10.0.0.4:50211", node4),
+        ])
+        .offline()
+        .operator(operator, move || secret.clone())
+        .build()?;
+
+        let node_of = |client: &Client| -> Result<AccountId, Error> {
+            let mut tx = client.transfer_crypto();
+            tx.transfer(operator, -500)
+                .transfer(AccountId::new(0, 0, 1002), 500);
+
+            let tx: proto::Transaction::Transaction = protobuf::parse_from_bytes(&tx.to_bytes()?)?;
+            Ok(tx.get_body().get_nodeAccountID().clone().into())
+        };
+
+        // A failing node is skipped
+        client.network.unreachable(node3);
+        assert_eq!(node_of(&client)?, node4);
+        assert_eq!(node_of(&client)?, node4);
+
+        // unless it is set on the client
+        client.set_node(node3);
+        assert_eq!(node_of(&client)?, node3);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_offline_error() -> Result<(), Error> {
+        // Without an operator there is no ID for the transaction
+        let client = Client::offline().node(AccountId::new(0, 0, 3)).build()?;
+
+        let mut tx = client.transfer_crypto();
+        tx.transfer(AccountId::new(0, 0, 1001), -500)
+            .transfer(AccountId::new(0, 0, 1002), 500);
+
+        let error = tx.to_bytes().unwrap_err();
+        match error.downcast_ref::<ErrorKind>() {
+            Some(ErrorKind::MissingField("operator")) => {}
+            _ => panic!("expected the operator to be missing, got: {}", error),
+        }
+
+        Ok(())
+    }
 }
@@ -0,0 +1,303 @@
+use crate::{
+    client::ClientBuilder, AccountId, Client, Endpoint, NetworkPreset, RetryPolicy, SecretKey,
+};
+use failure::{bail, format_err, Error, ResultExt};
+use serde::Deserialize;
+use std::{
+    collections::HashMap,
+    env, fs,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+// The configuration of a client, as read from a JSON or TOML file
+//
+//  network = "testnet"
+//  default_fee = 100000000
+//  request_timeout_ms = 30000
+//
+//  [operator]
+//  account = "0.0.2"
+//  key = { env = "OPERATOR_SECRET" }
+//
+//  [retry]
+//  max_retries = 5
+//  backoff_ms = 2000
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct Config {
+    network: NetworkConfig,
+    node: Option<String>,
+    operator: Option<OperatorConfig>,
+    default_fee: Option<u64>,
+    max_query_payment: Option<u64>,
+    retry: Option<RetryConfig>,
+    request_timeout_ms: Option<u64>,
+    connection_timeout_ms: Option<u64>,
+    #[serde(default)]
+    tls: bool,
+
+    // Key files are found relative to the configuration file
+    #[serde(skip)]
+    base_dir: PathBuf,
+}
+
+// Either the name of a preset or a map from the address of each node to its account
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum NetworkConfig {
+    Preset(String),
+    Nodes(HashMap<String, String>),
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct OperatorConfig {
+    account: String,
+    key: KeyConfig,
+}
+
+// Where to find the secret key of the operator
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "snake_case", deny_unknown_fields)]
+enum KeyConfig {
+    // The name of an environment variable holding the key
+    Env(String),
+    // The path of a file holding the key, in hex or PEM
+    File(PathBuf),
+    // The path of a keystore written by `SecretKey::to_encrypted`, and the name of an
+    // environment variable holding its password
+    Keystore { path: PathBuf, password_env: String },
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RetryConfig {
+    max_retries: usize,
+    backoff_ms: u64,
+}
+
+impl Config {
+    pub(crate) fn load(path: &Path) -> Result<Self, Error> {
+        let contents = fs::read_to_string(path)
+            .with_context(|_| format!("failed to read configuration from {}", path.display()))?;
+
+        let json = match path.extension().and_then(|extension| extension.to_str()) {
+            Some("json") => true,
+            Some("toml") => false,
+            _ => contents.trim_start().starts_with('{'),
+        };
+
+        let mut config = Self::parse(&contents, json)
+            .with_context(|_| format!("failed to parse configuration in {}", path.display()))?;
+
+        config.base_dir = path.parent().map(Path::to_path_buf).unwrap_or_default();
+
+        Ok(config)
+    }
+
+    fn parse(contents: &str, json: bool) -> Result<Self, Error> {
+        Ok(if json {
+            serde_json::from_str(contents)?
+        } else {
+            toml::from_str(contents)?
+        })
+    }
+
+    pub(crate) fn into_builder(self) -> Result<ClientBuilder, Error> {
+        let nodes = match self.network {
+            NetworkConfig::Preset(name) => name.parse::<NetworkPreset>()?.nodes(),
+
+            NetworkConfig::Nodes(nodes) => nodes
+                .iter()
+                .map(|(address, node)| Ok((address.parse::<Endpoint>()?, node.parse()?)))
+                .collect::<Result<Vec<(Endpoint, AccountId)>, Error>>()?,
+        };
+
+        let mut builder = Client::builder_for_network(nodes);
+
+        if let Some(node) = self.node {
+            builder = builder.node(node.parse()?);
+        }
+
+        if let Some(operator) = self.operator {
+            let secret = operator.key.load(&self.base_dir)?;
+            builder = builder.operator(operator.account.parse()?, move || secret.clone());
+        }
+
+        if let Some(fee) = self.default_fee {
+            builder = builder.default_fee(fee);
+        }
+
+        if let Some(max_query_payment) = self.max_query_payment {
+            builder = builder.max_query_payment(max_query_payment);
+        }
+
+        if let Some(retry) = self.retry {
+            builder = builder.retry(RetryPolicy {
+                max_retries: retry.max_retries,
+                backoff: Duration::from_millis(retry.backoff_ms),
+            });
+        }
+
+        if let Some(timeout) = self.request_timeout_ms {
+            builder = builder.request_timeout(Duration::from_millis(timeout));
+        }
+
+        if let Some(timeout) = self.connection_timeout_ms {
+            builder = builder.connection_timeout(Duration::from_millis(timeout));
+        }
+
+        if self.tls {
+            builder = builder.tls();
+        }
+
+        Ok(builder)
+    }
+}
+
+impl KeyConfig {
+    // The key is read once, when the configuration is loaded, so a missing key is
+    // reported up front rather than on the first transaction
+    fn load(&self, base_dir: &Path) -> Result<SecretKey, Error> {
+        let key = match self {
+            KeyConfig::Env(name) => env::var(name).map_err(|_| {
+                format_err!("the environment variable {} for the operator key is not set", name)
+            })?,
+
+            KeyConfig::File(path) => {
+                let path = base_dir.join(path);
+
+                fs::read_to_string(&path).with_context(|_| {
+                    format!("failed to read the operator key from {}", path.display())
+                })?
+            }
+
+            KeyConfig::Keystore { path, password_env } => {
+                let password = env::var(password_env).map_err(|_| {
+                    format_err!(
+                        "the environment variable {} for the keystore password is not set",
+                        password_env
+                    )
+                })?;
+
+                return SecretKey::from_keystore_file(base_dir.join(path), &password);
+            }
+        };
+
+        let key = key.trim();
+        if key.is_empty() {
+            bail!("the operator key is empty");
+        }
+
+        // A key file can be a PEM file, as written by OpenSSL, or hex
+        if key.starts_with("-----BEGIN ") {
+            SecretKey::from_pem(key)
+        } else {
+            key.parse()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Config, KeyConfig, NetworkConfig};
+    use failure::Error;
+
+    #[test]
+    fn test_parse_toml() -> Result<(), Error> {
+        let config = Config::parse(
+            r#"
+                network = "testnet"
+                default_fee = 50000000
+                request_timeout_ms = 30000
+
+                [operator]
+                account = "0.0.2"
+                key = { env = "OPERATOR_SECRET" }
+
+                [retry]
+                max_retries = 3
+                backoff_ms = 500
+            "#,
+            false,
+        )?;
+
+        match &config.network {
+            NetworkConfig::Preset(name) => assert_eq!(name, "testnet"),
+            network => panic!("unexpected network: {:?}", network),
+        }
+
+        let operator = config.operator.as_ref().unwrap();
+        assert_eq!(operator.account, "0.0.2");
+
+        match &operator.key {
+            KeyConfig::Env(name) => assert_eq!(name, "OPERATOR_SECRET"),
+            key => panic!("unexpected key: {:?}", key),
+        }
+
+        assert_eq!(config.default_fee, Some(50_000_000));
+        assert_eq!(config.request_timeout_ms, Some(30_000));
+        assert_eq!(config.retry.as_ref().unwrap().max_retries, 3);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_json() -> Result<(), Error> {
+        let config = Config::parse(
+            r#"{
+                "network": { "[fd00::3]:50211": "0.0.3", "35.186.191.247": "0.0.4" },
+                "node": "0.0.3",
+                "operator": { "account": "0.0.1001", "key": { "file": "operator.key" } },
+                "tls": true
+            }"#,
+            true,
+        )?;
+
+        match &config.network {
+            NetworkConfig::Nodes(nodes) => {
+                assert_eq!(nodes.len(), 2);
+                assert_eq!(nodes["35.186.191.247"], "0.0.4");
+            }
+
+            network => panic!("unexpected network: {:?}", network),
+        }
+
+        assert_eq!(config.node.as_ref().map(String::as_str), Some("0.0.3"));
+        assert!(config.tls);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_keystore() -> Result<(), Error> {
+        let config = Config::parse(
+            r#"
+                network = "testnet"
+
+                [operator]
+                account = "0.0.1001"
+                key = { keystore = { path = "operator.json", password_env = "OPERATOR_PASSWORD" } }
+            "#,
+            false,
+        )?;
+
+        match &config.operator.as_ref().unwrap().key {
+            KeyConfig::Keystore { path, password_env } => {
+                assert_eq!(path.to_str(), Some("operator.json"));
+                assert_eq!(password_env, "OPERATOR_PASSWORD");
+            }
+
+            key => panic!("unexpected key: {:?}", key),
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_unknown_field() {
+        assert!(Config::parse(r#"network = "testnet""#, false).is_ok());
+        assert!(Config::parse("network = \"testnet\"\nfee = 1", false).is_err());
+    }
+}
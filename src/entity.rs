@@ -10,6 +10,39 @@ pub enum Entity {
     Contract(ContractId),
 }
 
+/// The entities the network resolved for a Solidity address.
+///
+/// When the address belongs to a smart contract instance, both `contract_id` and the
+/// `account_id` associated with the instance are present.
+#[derive(Debug, Clone)]
+pub struct ResolvedSolidityId {
+    pub account_id: Option<AccountId>,
+    pub file_id: Option<FileId>,
+    pub contract_id: Option<ContractId>,
+}
+
+impl From<proto::GetBySolidityID::GetBySolidityIDResponse> for ResolvedSolidityId {
+    fn from(mut response: proto::GetBySolidityID::GetBySolidityIDResponse) -> Self {
+        Self {
+            account_id: if response.has_accountID() {
+                Some(response.take_accountID().into())
+            } else {
+                None
+            },
+            file_id: if response.has_fileID() {
+                Some(response.take_fileID().into())
+            } else {
+                None
+            },
+            contract_id: if response.has_contractID() {
+                Some(response.take_contractID().into())
+            } else {
+                None
+            },
+        }
+    }
+}
+
 pub(crate) fn try_into_entities(
     ids: RepeatedField<proto::GetByKey::EntityID>,
 ) -> Result<Vec<Entity>, Error> {
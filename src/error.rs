@@ -1,7 +1,8 @@
-use crate::Status;
+use crate::{AccountId, Status};
 use failure_derive::Fail;
+use std::time::Duration;
 
-#[derive(Debug, Fail)]
+#[derive(Debug, Clone, Fail)]
 pub enum ErrorKind {
     #[fail(display = "missing required field: `{}`", _0)]
     MissingField(&'static str),
@@ -11,4 +12,34 @@ pub enum ErrorKind {
 
     #[fail(display = "pre-check failed with status: {:?}", _0)]
     PreCheck(Status),
+
+    #[fail(display = "no address is known for node {}", _0)]
+    UnknownNode(AccountId),
+
+    #[fail(display = "client is offline; transactions can be signed but not sent to a node")]
+    Offline,
+
+    #[fail(display = "node did not respond within {:?}", _0)]
+    Timeout(Duration),
+
+    #[fail(display = "rate limit reached; capacity is available again in {:?}", _0)]
+    RateLimited(Duration),
+
+    #[fail(display = "transaction did not reach consensus within the receipt period")]
+    ReceiptTimeout,
+
+    #[fail(display = "query is not supported by any service on the node")]
+    UnsupportedQuery,
+
+    #[fail(display = "transaction is not supported by any service on the node")]
+    UnsupportedTransaction,
+
+    #[fail(
+        display = "query cost of {} tinybars exceeds the maximum payment of {} tinybars",
+        query_cost, max_query_payment
+    )]
+    MaxQueryPaymentExceeded {
+        query_cost: u64,
+        max_query_payment: u64,
+    },
 }
@@ -0,0 +1,434 @@
+use crate::{
+    functionality::{query_parts, transaction_functionality},
+    interceptor::{Call, Interceptor, Request, Response},
+    proto::{
+        self, Query::Query, QueryHeader::ResponseType, Transaction::Transaction,
+        TransactionResponse::TransactionResponse,
+    },
+    transport::Transport,
+    AccountId, ContractId, FileId, TransactionId,
+};
+use failure::{bail, Error, ResultExt};
+use futures::{
+    future::{self, BoxFuture},
+    FutureExt,
+};
+use parking_lot::Mutex;
+use protobuf::Message;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{HashMap, VecDeque},
+    fs::{self, File},
+    io::{LineWriter, Write},
+    path::Path,
+    time::Duration,
+};
+
+// A request and the response it got, as kept in a fixture file; one per line, in the
+// order the responses were received
+#[derive(Serialize, Deserialize)]
+struct Exchange {
+    kind: String,
+    entity: String,
+    // The protobuf encoding of the request and response, in hex
+    request: String,
+    response: String,
+    // The ID of a recorded transaction, to match the queries about it when replayed
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    transaction_id: Option<String>,
+}
+
+// Writes every request and its response to a fixture file
+pub(crate) struct Recorder(Mutex<LineWriter<File>>);
+
+impl Recorder {
+    pub(crate) fn create(path: &Path) -> Result<Self, Error> {
+        let file = File::create(path)
+            .with_context(|_| format!("failed to create fixture file {}", path.display()))?;
+
+        Ok(Recorder(Mutex::new(LineWriter::new(file))))
+    }
+}
+
+impl Interceptor for Recorder {
+    fn after_response(&self, call: &Call<'_>, response: Response<'_>, _: Duration) {
+        let response = match response {
+            Response::Query(response) => response.write_to_bytes(),
+            Response::Transaction(response) => response.write_to_bytes(),
+
+            // Nothing to replay
+            Response::Error(_) => return,
+        };
+
+        let request = match call.request() {
+            Request::Query(query) => query.write_to_bytes(),
+            Request::Transaction(tx) => tx.write_to_bytes(),
+        };
+
+        let (request, response) = match (request, response) {
+            (Ok(request), Ok(response)) => (request, response),
+            _ => return,
+        };
+
+        let transaction_id = match call.request() {
+            Request::Transaction(tx) => Some(transaction(tx.get_body().get_transactionID())),
+            Request::Query(_) => None,
+        };
+
+        let (kind, entity) = key(call.request());
+        let exchange = Exchange {
+            kind,
+            entity,
+            request: hex::encode(request),
+            response: hex::encode(response),
+            transaction_id,
+        };
+
+        // Serializing strings cannot fail
+        let line = serde_json::to_string(&exchange).unwrap();
+
+        if let Err(error) = writeln!(self.0.lock(), "{}", line) {
+            tracing::warn!(%error, "failed to record a response");
+        }
+    }
+}
+
+// Answers requests with the responses recorded in a fixture file, without a network.
+//
+// Requests are matched by their kind and the entity they are about, so the signatures,
+// payments and timestamps of a request do not need to match the recorded one. Requests
+// that match the same recording get its responses in the order they were recorded; the
+// last is repeated once the others are used up.
+//
+// Receipts and records are matched by the ID of their transaction. A transaction sent
+// during the replay has a new ID, so queries about it are matched with the ID of the
+// transaction it was answered with.
+pub(crate) struct Replay(Mutex<ReplayState>);
+
+#[derive(Default)]
+struct ReplayState {
+    recordings: HashMap<(String, String), VecDeque<Recording>>,
+    // The ID each transaction sent during the replay was recorded with
+    transaction_ids: HashMap<String, String>,
+}
+
+#[derive(Clone)]
+struct Recording {
+    response: Vec<u8>,
+    transaction_id: Option<String>,
+}
+
+impl Replay {
+    pub(crate) fn load(path: &Path) -> Result<Self, Error> {
+        let contents = fs::read_to_string(path)
+            .with_context(|_| format!("failed to read fixture file {}", path.display()))?;
+
+        let mut state = ReplayState::default();
+
+        for (index, line) in contents.lines().enumerate() {
+            if line.trim().is_empty() {
+                continue;
+            }
+
+            let context = || format!("invalid fixture on line {} of {}", index + 1, path.display());
+
+            let exchange: Exchange = serde_json::from_str(line).with_context(|_| context())?;
+            let response = hex::decode(&exchange.response).with_context(|_| context())?;
+
+            state
+                .recordings
+                .entry((exchange.kind, exchange.entity))
+                .or_default()
+                .push_back(Recording {
+                    response,
+                    transaction_id: exchange.transaction_id,
+                });
+        }
+
+        Ok(Replay(Mutex::new(state)))
+    }
+
+    fn respond<M: Message>(&self, request: Request<'_>) -> Result<M, Error> {
+        let (kind, mut entity) = key(request);
+
+        let mut state = self.0.lock();
+
+        if let Some(recorded) = state.transaction_ids.get(&entity) {
+            entity = recorded.clone();
+        }
+
+        let recording = match state.recordings.get_mut(&(kind.clone(), entity.clone())) {
+            Some(queue) => {
+                if queue.len() > 1 {
+                    queue.pop_front().unwrap()
+                } else {
+                    queue[0].clone()
+                }
+            }
+
+            None => bail!("no recorded response matches {} of {}", kind, entity),
+        };
+
+        if let (Request::Transaction(tx), Some(recorded)) = (request, recording.transaction_id) {
+            let id = transaction(tx.get_body().get_transactionID());
+            state.transaction_ids.insert(id, recorded);
+        }
+
+        Ok(protobuf::parse_from_bytes(&recording.response)?)
+    }
+}
+
+impl Transport for Replay {
+    fn submit(
+        &self,
+        tx: Transaction,
+        _: &[(String, String)],
+    ) -> BoxFuture<'static, Result<TransactionResponse, Error>> {
+        future::ready(self.respond(Request::Transaction(&tx))).boxed()
+    }
+
+    fn query(
+        &self,
+        query: Query,
+        _: &[(String, String)],
+    ) -> BoxFuture<'static, Result<proto::Response::Response, Error>> {
+        future::ready(self.respond(Request::Query(&query))).boxed()
+    }
+}
+
+// The kind of a request and the entity it is about, which is all a replayed request
+// needs to match
+fn key(request: Request<'_>) -> (String, String) {
+    match request {
+        Request::Transaction(tx) => (
+            format!("{:?}", transaction_functionality(tx)),
+            transaction_entity(tx),
+        ),
+
+        Request::Query(query) => {
+            use self::proto::Query::Query_oneof_query::transactionGetFastRecord;
+
+            let (functionality, header) = query_parts(query);
+
+            // Fast records share their functionality with records, but not their response
+            let fast = match query.query {
+                Some(transactionGetFastRecord(_)) => "Fast",
+                _ => "",
+            };
+
+            // The cost of a query is answered differently from the query itself
+            let cost = match header.map(|header| header.get_responseType()) {
+                Some(ResponseType::COST_ANSWER) => " (cost)",
+                _ => "",
+            };
+
+            (
+                format!("{}{:?}{}", fast, functionality, cost),
+                query_entity(query),
+            )
+        }
+    }
+}
+
+fn transaction_entity(tx: &Transaction) -> String {
+    use self::proto::TransactionBody::TransactionBody_oneof_data::*;
+
+    let body = tx.get_body();
+
+    match &body.data {
+        Some(cryptoUpdateAccount(data)) => account(data.get_accountIDToUpdate()),
+        Some(cryptoDelete(data)) => account(data.get_deleteAccountID()),
+        Some(fileAppend(data)) => file(data.get_fileID()),
+        Some(fileUpdate(data)) => file(data.get_fileID()),
+        Some(fileDelete(data)) => file(data.get_fileID()),
+        Some(contractCall(data)) => contract(data.get_contractID()),
+        Some(contractUpdateInstance(data)) => contract(data.get_contractID()),
+        Some(contractDeleteInstance(data)) => contract(data.get_contractID()),
+
+        // Anything else creates an entity, or is about many of them, so is matched by
+        // who pays for it
+        _ => account(body.get_transactionID().get_accountID()),
+    }
+}
+
+fn query_entity(query: &Query) -> String {
+    use self::proto::Query::Query_oneof_query::*;
+
+    match &query.query {
+        Some(cryptogetAccountBalance(q)) => account(q.get_accountID()),
+        Some(cryptoGetAccountRecords(q)) => account(q.get_accountID()),
+        Some(cryptoGetInfo(q)) => account(q.get_accountID()),
+        Some(cryptoGetClaim(q)) => account(q.get_accountID()),
+        Some(cryptoGetProxyStakers(q)) => account(q.get_accountID()),
+        Some(fileGetContents(q)) => file(q.get_fileID()),
+        Some(fileGetInfo(q)) => file(q.get_fileID()),
+        Some(contractCallLocal(q)) => contract(q.get_contractID()),
+        Some(contractGetInfo(q)) => contract(q.get_contractID()),
+        Some(contractGetBytecode(q)) => contract(q.get_contractID()),
+        Some(ContractGetRecords(q)) => contract(q.get_contractID()),
+        Some(getBySolidityID(q)) => q.get_solidityID().to_owned(),
+
+        Some(transactionGetReceipt(q)) => transaction(q.get_transactionID()),
+        Some(transactionGetRecord(q)) => transaction(q.get_transactionID()),
+        Some(transactionGetFastRecord(q)) => transaction(q.get_transactionID()),
+
+        Some(getByKey(_)) | None => String::new(),
+    }
+}
+
+fn account(id: &proto::BasicTypes::AccountID) -> String {
+    AccountId::from(id.clone()).to_string()
+}
+
+fn file(id: &proto::BasicTypes::FileID) -> String {
+    FileId::from(id.clone()).to_string()
+}
+
+fn contract(id: &proto::BasicTypes::ContractID) -> String {
+    ContractId::from(id.clone()).to_string()
+}
+
+// The payer and valid start of a transaction
+fn transaction(id: &proto::BasicTypes::TransactionID) -> String {
+    TransactionId::from(id.clone()).to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Recorder, Replay};
+    use crate::{
+        interceptor::{Call, Interceptor, Request, Response},
+        proto::{self, ToProto},
+        transport::Transport,
+        AccountId, TransactionId,
+    };
+    use chrono::{TimeZone, Utc};
+    use futures::executor::block_on;
+    use std::{env, fs, time::Duration};
+
+    fn transaction_id(valid_start: i64) -> proto::BasicTypes::TransactionID {
+        TransactionId {
+            account_id: AccountId::new(0, 0, 2),
+            transaction_valid_start: Utc.timestamp(valid_start, 0),
+        }
+        .to_proto()
+        .unwrap()
+    }
+
+    fn transfer(valid_start: i64) -> proto::Transaction::Transaction {
+        let mut tx = proto::Transaction::Transaction::new();
+        tx.mut_body().set_transactionID(transaction_id(valid_start));
+        tx.mut_body().mut_cryptoTransfer();
+        tx
+    }
+
+    fn receipt_query(valid_start: i64) -> proto::Query::Query {
+        let mut query = proto::TransactionGetReceipt::TransactionGetReceiptQuery::new();
+        query.set_transactionID(transaction_id(valid_start));
+
+        let mut wrapper = proto::Query::Query::new();
+        wrapper.set_transactionGetReceipt(query);
+
+        wrapper
+    }
+
+    fn balance_query(account: AccountId, payment: i32) -> proto::Query::Query {
+        let mut payment_tx = proto::Transaction::Transaction::new();
+        payment_tx.mut_body().set_transactionID(
+            TransactionId::new(AccountId::new(0, 0, 2)).to_proto().unwrap(),
+        );
+
+        let mut signature = proto::BasicTypes::Signature::new();
+        signature.set_ed25519(vec![payment as u8; 64]);
+        payment_tx.mut_sigs().mut_sigs().push(signature);
+
+        let mut query = proto::CryptoGetAccountBalance::CryptoGetAccountBalanceQuery::new();
+        query.set_accountID(account.to_proto().unwrap());
+        query.mut_header().set_payment(payment_tx);
+
+        let mut wrapper = proto::Query::Query::new();
+        wrapper.set_cryptogetAccountBalance(query);
+
+        wrapper
+    }
+
+    #[test]
+    fn test_record_and_replay() {
+        let path = env::temp_dir().join(format!("hedera-fixture-{}.jsonl", std::process::id()));
+        let account = AccountId::new(0, 0, 1001);
+
+        let recorder = Recorder::create(&path).unwrap();
+
+        for balance in &[100, 200] {
+            let query = balance_query(account, 1);
+
+            let mut response = proto::Response::Response::new();
+            response.mut_cryptogetAccountBalance().set_balance(*balance);
+
+            let call = Call::new(Request::Query(&query), None, 1);
+            let latency = Duration::from_millis(1);
+            recorder.after_response(&call, Response::Query(&response), latency);
+        }
+
+        drop(recorder);
+
+        let replay = Replay::load(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        // A different payment still matches; the last response is repeated
+        for expected in &[100, 200, 200] {
+            let query = balance_query(account, 2);
+            let response = block_on(replay.query(query, &[])).unwrap();
+
+            assert_eq!(response.get_cryptogetAccountBalance().get_balance(), *expected);
+        }
+
+        let other = balance_query(AccountId::new(0, 0, 1002), 1);
+        assert!(block_on(replay.query(other, &[])).is_err());
+    }
+
+    #[test]
+    fn test_replay_receipts() {
+        use self::proto::ResponseCode::ResponseCodeEnum::{INVALID_SIGNATURE, SUCCESS};
+
+        let path = env::temp_dir().join(format!("hedera-receipts-{}.jsonl", std::process::id()));
+        let recorder = Recorder::create(&path).unwrap();
+        let latency = Duration::from_millis(1);
+
+        // Two transactions from the same payer, each with its own receipt
+        for (valid_start, status) in &[(100, SUCCESS), (200, INVALID_SIGNATURE)] {
+            let tx = transfer(*valid_start);
+            let response = proto::TransactionResponse::TransactionResponse::new();
+            let call = Call::new(Request::Transaction(&tx), None, 1);
+            recorder.after_response(&call, Response::Transaction(&response), latency);
+
+            let query = receipt_query(*valid_start);
+            let mut response = proto::Response::Response::new();
+            response
+                .mut_transactionGetReceipt()
+                .mut_receipt()
+                .set_status(*status);
+
+            let call = Call::new(Request::Query(&query), None, 1);
+            recorder.after_response(&call, Response::Query(&response), latency);
+        }
+
+        drop(recorder);
+
+        let replay = Replay::load(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        // Replayed transactions have new IDs; the receipt of each is the one recorded for
+        // the transaction it was answered as
+        for (valid_start, status) in &[(300, SUCCESS), (400, INVALID_SIGNATURE)] {
+            block_on(replay.submit(transfer(*valid_start), &[])).unwrap();
+
+            let response = block_on(replay.query(receipt_query(*valid_start), &[])).unwrap();
+            let receipt = response.get_transactionGetReceipt().get_receipt();
+
+            assert_eq!(receipt.get_status(), *status);
+        }
+
+        assert!(block_on(replay.query(receipt_query(500), &[])).is_err());
+    }
+}
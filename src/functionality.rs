@@ -0,0 +1,73 @@
+use crate::proto::{self, BasicTypes::HederaFunctionality, QueryHeader::QueryHeader};
+
+// The kind of a transaction, as used to label it in metrics and traces
+pub(crate) fn transaction_functionality(
+    tx: &proto::Transaction::Transaction,
+) -> HederaFunctionality {
+    use self::proto::TransactionBody::TransactionBody_oneof_data::*;
+
+    match &tx.get_body().data {
+        Some(contractCall(_)) => HederaFunctionality::ContractCall,
+        Some(contractCreateInstance(_)) => HederaFunctionality::ContractCreate,
+        Some(contractUpdateInstance(_)) => HederaFunctionality::ContractUpdate,
+        Some(contractDeleteInstance(_)) => HederaFunctionality::ContractDelete,
+        Some(cryptoAddClaim(_)) => HederaFunctionality::CryptoAddClaim,
+        Some(cryptoCreateAccount(_)) => HederaFunctionality::CryptoCreate,
+        Some(cryptoDelete(_)) => HederaFunctionality::CryptoDelete,
+        Some(cryptoDeleteClaim(_)) => HederaFunctionality::CryptoDeleteClaim,
+        Some(cryptoTransfer(_)) => HederaFunctionality::CryptoTransfer,
+        Some(cryptoUpdateAccount(_)) => HederaFunctionality::CryptoUpdate,
+        Some(fileAppend(_)) => HederaFunctionality::FileAppend,
+        Some(fileCreate(_)) => HederaFunctionality::FileCreate,
+        Some(fileDelete(_)) => HederaFunctionality::FileDelete,
+        Some(fileUpdate(_)) => HederaFunctionality::FileUpdate,
+        Some(systemDelete(_)) => HederaFunctionality::SystemDelete,
+        Some(systemUndelete(_)) => HederaFunctionality::SystemUndelete,
+        Some(freeze(_)) => HederaFunctionality::Freeze,
+        None => HederaFunctionality::NONE,
+    }
+}
+
+// The kind and header of a query
+pub(crate) fn query_parts(
+    query: &proto::Query::Query,
+) -> (HederaFunctionality, Option<&QueryHeader>) {
+    use self::proto::Query::Query_oneof_query::*;
+
+    match &query.query {
+        Some(getByKey(q)) => (HederaFunctionality::GetByKey, q.header.as_ref()),
+        Some(getBySolidityID(q)) => (HederaFunctionality::GetBySolidityID, q.header.as_ref()),
+        Some(contractCallLocal(q)) => (HederaFunctionality::ContractCallLocal, q.header.as_ref()),
+        Some(contractGetInfo(q)) => (HederaFunctionality::ContractGetInfo, q.header.as_ref()),
+        Some(contractGetBytecode(q)) => {
+            (HederaFunctionality::ContractGetBytecode, q.header.as_ref())
+        }
+        Some(ContractGetRecords(q)) => {
+            (HederaFunctionality::ContractGetRecords, q.header.as_ref())
+        }
+        Some(cryptogetAccountBalance(q)) => {
+            (HederaFunctionality::CryptoGetAccountBalance, q.header.as_ref())
+        }
+        Some(cryptoGetAccountRecords(q)) => {
+            (HederaFunctionality::CryptoGetAccountRecords, q.header.as_ref())
+        }
+        Some(cryptoGetInfo(q)) => (HederaFunctionality::CryptoGetInfo, q.header.as_ref()),
+        Some(cryptoGetClaim(q)) => (HederaFunctionality::CryptoGetClaim, q.header.as_ref()),
+        Some(cryptoGetProxyStakers(q)) => {
+            (HederaFunctionality::CryptoGetStakers, q.header.as_ref())
+        }
+        Some(fileGetContents(q)) => (HederaFunctionality::FileGetContents, q.header.as_ref()),
+        Some(fileGetInfo(q)) => (HederaFunctionality::FileGetInfo, q.header.as_ref()),
+        Some(transactionGetReceipt(q)) => {
+            (HederaFunctionality::TransactionGetReceipt, q.header.as_ref())
+        }
+        Some(transactionGetRecord(q)) => {
+            (HederaFunctionality::TransactionGetRecord, q.header.as_ref())
+        }
+        // There is no separate functionality for fast records
+        Some(transactionGetFastRecord(q)) => {
+            (HederaFunctionality::TransactionGetRecord, q.header.as_ref())
+        }
+        None => (HederaFunctionality::NONE, None),
+    }
+}
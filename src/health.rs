@@ -0,0 +1,160 @@
+use crate::{AccountId, Status};
+use parking_lot::Mutex;
+use std::{
+    cmp::min,
+    collections::HashMap,
+    time::{Duration, Instant},
+};
+
+// How long a node is excluded after its first failure; doubled for every further
+// failure in a row, up to the maximum
+const BASE_BACKOFF: Duration = Duration::from_secs(1);
+const MAX_BACKOFF: Duration = Duration::from_secs(60);
+
+/// The health of a node, as seen by the client.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NodeHealth {
+    pub node: AccountId,
+
+    /// The number of requests in a row that failed because the node was busy, not active,
+    /// or could not be reached.
+    pub consecutive_failures: usize,
+
+    /// Until when the node is skipped when picking a node for a request, if it is excluded.
+    pub excluded_until: Option<Instant>,
+}
+
+impl NodeHealth {
+    /// Whether the node is picked for new requests.
+    pub fn is_healthy(&self) -> bool {
+        self.excluded_until
+            .map_or(true, |excluded_until| excluded_until <= Instant::now())
+    }
+}
+
+#[derive(Default)]
+struct State {
+    consecutive_failures: usize,
+    excluded_until: Option<Instant>,
+}
+
+// Counts the failures of each node and excludes a failing node for a while
+#[derive(Default)]
+pub(crate) struct HealthTracker {
+    states: Mutex<HashMap<AccountId, State>>,
+}
+
+impl HealthTracker {
+    // Whether a precheck status means the node could not handle the request,
+    // rather than the request being at fault
+    pub(crate) fn is_node_failure(status: Status) -> bool {
+        match status {
+            Status::Busy | Status::PlatformNotActive => true,
+            _ => false,
+        }
+    }
+
+    pub(crate) fn success(&self, node: AccountId) {
+        self.states.lock().remove(&node);
+    }
+
+    pub(crate) fn failure(&self, node: AccountId) {
+        let mut states = self.states.lock();
+        let state = states.entry(node).or_default();
+
+        state.consecutive_failures += 1;
+
+        let exponent = min(state.consecutive_failures - 1, 6) as u32;
+        let backoff = min(BASE_BACKOFF * 2u32.pow(exponent), MAX_BACKOFF);
+
+        state.excluded_until = Some(Instant::now() + backoff);
+
+        tracing::debug!(
+            %node,
+            ?backoff,
+            failures = state.consecutive_failures,
+            "excluding node"
+        );
+    }
+
+    pub(crate) fn is_excluded(&self, node: AccountId, now: Instant) -> bool {
+        self.states
+            .lock()
+            .get(&node)
+            .and_then(|state| state.excluded_until)
+            .map_or(false, |excluded_until| excluded_until > now)
+    }
+
+    // When the exclusion of the node ends, for picking the node that recovers first
+    // when every node is excluded
+    pub(crate) fn excluded_until(&self, node: AccountId) -> Option<Instant> {
+        self.states
+            .lock()
+            .get(&node)
+            .and_then(|state| state.excluded_until)
+    }
+
+    pub(crate) fn snapshot(&self, nodes: &[AccountId]) -> Vec<NodeHealth> {
+        let states = self.states.lock();
+
+        nodes
+            .iter()
+            .map(|node| match states.get(node) {
+                Some(state) => NodeHealth {
+                    node: *node,
+                    consecutive_failures: state.consecutive_failures,
+                    excluded_until: state.excluded_until,
+                },
+
+                None => NodeHealth {
+                    node: *node,
+                    consecutive_failures: 0,
+                    excluded_until: None,
+                },
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{HealthTracker, MAX_BACKOFF};
+    use crate::AccountId;
+    use std::time::{Duration, Instant};
+
+    #[test]
+    fn test_exclusion() {
+        let tracker = HealthTracker::default();
+        let node = AccountId::new(0, 0, 3);
+
+        assert!(!tracker.is_excluded(node, Instant::now()));
+
+        tracker.failure(node);
+        assert!(tracker.is_excluded(node, Instant::now()));
+        assert!(!tracker.is_excluded(node, Instant::now() + Duration::from_secs(2)));
+
+        tracker.failure(node);
+        assert!(tracker.is_excluded(node, Instant::now() + Duration::from_secs(1)));
+
+        let health = tracker.snapshot(&[node]);
+        assert_eq!(health[0].consecutive_failures, 2);
+        assert!(!health[0].is_healthy());
+
+        tracker.success(node);
+        assert!(!tracker.is_excluded(node, Instant::now()));
+        assert!(tracker.snapshot(&[node])[0].is_healthy());
+    }
+
+    #[test]
+    fn test_backoff_limit() {
+        let tracker = HealthTracker::default();
+        let node = AccountId::new(0, 0, 3);
+
+        for _ in 0..20 {
+            tracker.failure(node);
+        }
+
+        let excluded_until = tracker.excluded_until(node).unwrap();
+        assert!(excluded_until <= Instant::now() + MAX_BACKOFF);
+    }
+}
@@ -1,6 +1,6 @@
 macro_rules! define_id {
     ($field:ident, $name:ident, $proto:ident, $method_set:ident, $method_get:ident) => {
-        #[derive(Debug, PartialEq, Clone, Copy)]
+        #[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
         #[repr(C)]
         pub struct $name {
             pub shard: i64,
@@ -1,4 +1,4 @@
-use crate::{crypto::PublicKey, proto, AccountId, Claim, ContractId, FileId};
+use crate::{crypto::PublicKey, proto, AccountId, Claim, ContractId, ErrorKind, FileId};
 use chrono::{DateTime, Utc};
 use failure::Error;
 use std::time::Duration;
@@ -53,6 +53,27 @@ impl TryFrom<proto::CryptoGetInfo::CryptoGetInfoResponse_AccountInfo> for Accoun
     }
 }
 
+#[derive(Debug)]
+pub struct ProxyStaker {
+    pub account_id: AccountId,
+    pub amount: i64,
+}
+
+impl TryFrom<proto::CryptoGetStakers::ProxyStaker> for ProxyStaker {
+    type Err = Error;
+
+    fn try_from(mut staker: proto::CryptoGetStakers::ProxyStaker) -> Result<Self, Error> {
+        if !staker.has_accountID() {
+            Err(ErrorKind::MissingField("accountID"))?;
+        }
+
+        Ok(Self {
+            account_id: staker.take_accountID().into(),
+            amount: staker.get_amount(),
+        })
+    }
+}
+
 #[derive(Debug)]
 pub struct ContractInfo {
     pub contract_id: ContractId,
@@ -115,3 +136,29 @@ impl TryFrom<proto::FileGetInfo::FileGetInfoResponse_FileInfo> for FileInfo {
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::ProxyStaker;
+    use crate::{
+        proto::{self, ToProto},
+        AccountId,
+    };
+    use failure::Error;
+    use try_from::TryInto;
+
+    #[test]
+    fn test_proxy_staker() -> Result<(), Error> {
+        let mut staker = proto::CryptoGetStakers::ProxyStaker::new();
+        staker.set_amount(500);
+        assert!(TryInto::<ProxyStaker>::try_into(staker.clone()).is_err());
+
+        staker.set_accountID(AccountId::new(0, 0, 1001).to_proto()?);
+        let staker: ProxyStaker = staker.try_into()?;
+
+        assert_eq!(staker.account_id, AccountId::new(0, 0, 1001));
+        assert_eq!(staker.amount, 500);
+
+        Ok(())
+    }
+}
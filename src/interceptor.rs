@@ -0,0 +1,153 @@
+use crate::{proto, AccountId};
+use failure::Error;
+use std::time::Duration;
+
+/// Observes every request a client sends to a node, and every response to it.
+///
+/// Interceptors are registered with `ClientBuilder::interceptor` and are called in the
+/// order they were registered, for each attempt at sending a request.
+pub trait Interceptor: Send + Sync {
+    /// Called before a request is sent. Returning an error vetoes the request, which then
+    /// fails with that error without being sent.
+    fn before_send(&self, call: &mut Call<'_>) -> Result<(), Error> {
+        let _ = call;
+        Ok(())
+    }
+
+    /// Called when the node responds to a request, or when the request fails without
+    /// a response, with the time since it was sent.
+    fn after_response(&self, call: &Call<'_>, response: Response<'_>, latency: Duration) {
+        let _ = (call, response, latency);
+    }
+}
+
+/// A request the client is about to send.
+#[derive(Debug, Clone, Copy)]
+pub enum Request<'a> {
+    Query(&'a proto::Query::Query),
+    Transaction(&'a proto::Transaction::Transaction),
+}
+
+/// The outcome of sending a request.
+#[derive(Debug, Clone, Copy)]
+pub enum Response<'a> {
+    Query(&'a proto::Response::Response),
+    Transaction(&'a proto::TransactionResponse::TransactionResponse),
+    /// The request failed or timed out without a response from the node.
+    Error(&'a Error),
+}
+
+/// A single attempt at sending a request to a node.
+#[derive(Debug)]
+pub struct Call<'a> {
+    request: Request<'a>,
+    node: Option<AccountId>,
+    attempt: usize,
+    annotations: Vec<(String, String)>,
+}
+
+impl<'a> Call<'a> {
+    pub(crate) fn new(request: Request<'a>, node: Option<AccountId>, attempt: usize) -> Self {
+        Self {
+            request,
+            node,
+            attempt,
+            annotations: Vec::new(),
+        }
+    }
+
+    pub fn request(&self) -> Request<'a> {
+        self.request
+    }
+
+    /// The node the request is sent to, if it is known. It is not known when a client
+    /// created from a single address has no node set.
+    pub fn node(&self) -> Option<AccountId> {
+        self.node
+    }
+
+    /// The attempt at sending the request, starting from 1. Requests are sent again when
+    /// the node is busy.
+    pub fn attempt(&self) -> usize {
+        self.attempt
+    }
+
+    /// Annotate the request; annotations are given to the transport, which sends them to
+    /// the node as gRPC metadata, and seen by interceptors registered after this one.
+    pub fn annotate(&mut self, key: impl Into<String>, value: impl Into<String>) {
+        self.annotations.push((key.into(), value.into()));
+    }
+
+    pub fn annotations(&self) -> &[(String, String)] {
+        &self.annotations
+    }
+}
+
+// The interceptors registered with a client
+#[derive(Default)]
+pub(crate) struct Interceptors(pub(crate) Vec<Box<dyn Interceptor>>);
+
+impl Interceptors {
+    pub(crate) fn before_send(&self, call: &mut Call<'_>) -> Result<(), Error> {
+        for interceptor in &self.0 {
+            interceptor.before_send(call)?;
+        }
+
+        Ok(())
+    }
+
+    pub(crate) fn after_response(
+        &self,
+        call: &Call<'_>,
+        response: Response<'_>,
+        latency: Duration,
+    ) {
+        for interceptor in &self.0 {
+            interceptor.after_response(call, response, latency);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Call, Interceptor, Interceptors, Request};
+    use crate::proto;
+    use failure::{bail, Error};
+
+    struct Tag;
+
+    impl Interceptor for Tag {
+        fn before_send(&self, call: &mut Call<'_>) -> Result<(), Error> {
+            call.annotate("x-request-tag", "test");
+            Ok(())
+        }
+    }
+
+    struct Veto;
+
+    impl Interceptor for Veto {
+        fn before_send(&self, call: &mut Call<'_>) -> Result<(), Error> {
+            if call.annotations().is_empty() {
+                bail!("untagged request");
+            }
+
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_annotate_and_veto() {
+        let query = proto::Query::Query::new();
+
+        let mut call = Call::new(Request::Query(&query), None, 1);
+        assert!(Interceptors(vec![Box::new(Veto)]).before_send(&mut call).is_err());
+
+        let mut call = Call::new(Request::Query(&query), None, 1);
+        let interceptors = Interceptors(vec![Box::new(Tag), Box::new(Veto)]);
+        assert!(interceptors.before_send(&mut call).is_ok());
+        assert_eq!(
+            call.annotations(),
+            &[("x-request-tag".to_owned(), "test".to_owned())][..]
+        );
+    }
+}
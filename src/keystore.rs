@@ -0,0 +1,161 @@
+use failure::{bail, format_err, Error, ResultExt};
+use ring::{
+    aead::{self, Aad, Nonce, OpeningKey, SealingKey},
+    digest, pbkdf2,
+    rand::{SecureRandom, SystemRandom},
+};
+use serde::{Deserialize, Serialize};
+use std::num::NonZeroU32;
+
+const VERSION: u32 = 1;
+
+const KDF: &str = "pbkdf2-hmac-sha256";
+const CIPHER: &str = "aes-256-gcm";
+
+// Slow enough to make guessing passwords expensive while unlocking a key takes well under
+// a second; kept in each keystore so it can be raised without breaking older ones
+const ITERATIONS: u32 = 262_144;
+
+// A keystore asking for more is refused rather than taking minutes to unlock, as a
+// modified one could
+const MAX_ITERATIONS: u32 = 16 * ITERATIONS;
+
+const SALT_LENGTH: usize = 32;
+const KEY_LENGTH: usize = 32;
+
+// A secret key encrypted with a password, as JSON.
+//
+// The key to encrypt with is derived from the password with PBKDF2, and the PKCS#8
+// encoding of the secret key is encrypted with AES-256-GCM, so a wrong password or a
+// changed keystore is detected rather than giving back a different key. Binary values
+// are in hex.
+#[derive(Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Keystore {
+    version: u32,
+    kdf: KdfParams,
+    cipher: CipherParams,
+    ciphertext: String,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct KdfParams {
+    name: String,
+    iterations: u32,
+    salt: String,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct CipherParams {
+    name: String,
+    nonce: String,
+}
+
+// Encrypt the DER encoding of a secret key with a password
+pub(crate) fn encrypt(der: &[u8], password: &str) -> Result<Vec<u8>, Error> {
+    let random = SystemRandom::new();
+
+    let mut salt = [0; SALT_LENGTH];
+    let mut nonce = [0; aead::NONCE_LEN];
+
+    random
+        .fill(&mut salt)
+        .and_then(|_| random.fill(&mut nonce))
+        .map_err(|_| format_err!("failed to generate a salt and nonce for the keystore"))?;
+
+    // note: cannot fail; the iterations are not zero
+    let iterations = NonZeroU32::new(ITERATIONS).unwrap();
+    let key = derive_key(iterations, &salt, password);
+
+    // note: cannot fail; the key is the length AES-256 needs
+    let key = SealingKey::new(&aead::AES_256_GCM, &key).unwrap();
+
+    let tag_len = aead::AES_256_GCM.tag_len();
+    let mut ciphertext = der.to_vec();
+    ciphertext.resize(der.len() + tag_len, 0);
+
+    aead::seal_in_place(
+        &key,
+        Nonce::assume_unique_for_key(nonce),
+        Aad::empty(),
+        &mut ciphertext,
+        tag_len,
+    )
+    .map_err(|_| format_err!("failed to encrypt the keystore"))?;
+
+    let keystore = Keystore {
+        version: VERSION,
+        kdf: KdfParams {
+            name: KDF.to_owned(),
+            iterations: ITERATIONS,
+            salt: hex::encode(salt),
+        },
+        cipher: CipherParams {
+            name: CIPHER.to_owned(),
+            nonce: hex::encode(nonce),
+        },
+        ciphertext: hex::encode(ciphertext),
+    };
+
+    Ok(serde_json::to_vec_pretty(&keystore)?)
+}
+
+// Decrypt the DER encoding of a secret key with a password
+pub(crate) fn decrypt(keystore: &[u8], password: &str) -> Result<Vec<u8>, Error> {
+    let keystore: Keystore = serde_json::from_slice(keystore).context("invalid keystore")?;
+
+    if keystore.version != VERSION {
+        bail!("unsupported keystore version: {}", keystore.version);
+    }
+
+    if keystore.kdf.name != KDF {
+        bail!("unsupported keystore key derivation: {}", keystore.kdf.name);
+    }
+
+    if keystore.cipher.name != CIPHER {
+        bail!("unsupported keystore cipher: {}", keystore.cipher.name);
+    }
+
+    if keystore.kdf.iterations > MAX_ITERATIONS {
+        bail!(
+            "invalid keystore: {} iterations is more than the maximum of {}",
+            keystore.kdf.iterations,
+            MAX_ITERATIONS
+        );
+    }
+
+    let iterations = NonZeroU32::new(keystore.kdf.iterations)
+        .ok_or_else(|| format_err!("invalid keystore: iterations must not be zero"))?;
+
+    let salt = hex::decode(&keystore.kdf.salt).context("invalid keystore salt")?;
+    let nonce = hex::decode(&keystore.cipher.nonce).context("invalid keystore nonce")?;
+    let mut ciphertext = hex::decode(&keystore.ciphertext).context("invalid keystore ciphertext")?;
+
+    let nonce = Nonce::try_assume_unique_for_key(&nonce)
+        .map_err(|_| format_err!("invalid keystore nonce"))?;
+
+    let key = derive_key(iterations, &salt, password);
+
+    // note: cannot fail; the key is the length AES-256 needs
+    let key = OpeningKey::new(&aead::AES_256_GCM, &key).unwrap();
+
+    let der = aead::open_in_place(&key, nonce, Aad::empty(), 0, &mut ciphertext)
+        .map_err(|_| format_err!("wrong password, or the keystore has been modified"))?;
+
+    Ok(der.to_vec())
+}
+
+fn derive_key(iterations: NonZeroU32, salt: &[u8], password: &str) -> [u8; KEY_LENGTH] {
+    let mut key = [0; KEY_LENGTH];
+    pbkdf2::derive(
+        &digest::SHA256,
+        iterations,
+        salt,
+        password.as_bytes(),
+        &mut key,
+    );
+
+    key
+}
@@ -4,41 +4,72 @@
 #[macro_use]
 mod macros;
 
+mod account_watcher;
+mod address_book;
 mod argument;
+mod cache;
 pub mod call_params;
 mod call_param_utils;
 mod claim;
 pub mod client;
+mod config;
 mod crypto;
 mod duration;
 mod entity;
 mod error;
+mod fixture;
+mod functionality;
+mod health;
 mod id;
 mod info;
+pub mod interceptor;
+mod keystore;
+pub mod messages;
+#[cfg(feature = "metrics")]
+pub mod metrics;
+#[cfg(feature = "mock")]
+pub mod mock;
+mod network;
+mod pem;
 mod proto;
 pub mod query;
+mod rate_limit;
+mod redact;
+mod retry;
 pub mod status;
 pub mod solidity_util;
+mod timeout;
 mod timestamp;
+mod tls;
 pub mod transaction;
 mod transaction_id;
 mod transaction_receipt;
 mod transaction_record;
+mod transport;
 pub mod function_result;
 pub mod function_selector;
 
 pub use self::{
+    account_watcher::{AccountUpdate, AccountWatcher},
+    address_book::{NodeAddress, NodeAddressBook},
+    cache::{CachedEntity, CachedQuery, QueryCache},
     claim::Claim,
     client::Client,
     crypto::{PublicKey, SecretKey, Signature},
-    entity::Entity,
+    entity::{Entity, ResolvedSolidityId},
     error::ErrorKind,
+    health::NodeHealth,
     id::*,
-    info::{AccountInfo, ContractInfo, FileInfo},
+    info::{AccountInfo, ContractInfo, FileInfo, ProxyStaker},
+    network::{Endpoint, NetworkPreset},
+    proto::BasicTypes::HederaFunctionality,
+    rate_limit::{RateLimit, RateLimiter},
+    retry::RetryPolicy,
     status::Status,
     transaction_id::TransactionId,
     transaction_receipt::TransactionReceipt,
     transaction_record::{TransactionRecord, TransactionRecordBody},
+    transport::Transport,
 };
 
 use once_cell::{sync::Lazy};
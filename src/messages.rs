@@ -0,0 +1,9 @@
+//! The protobuf messages a client exchanges with a node.
+//!
+//! These are the requests and responses a `Transport` carries and interceptors observe; the
+//! rest of the protobuf types of the network stay internal to the crate.
+
+pub use crate::proto::{
+    Query::Query, Response::Response, Transaction::Transaction,
+    TransactionResponse::TransactionResponse,
+};
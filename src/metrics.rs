@@ -0,0 +1,200 @@
+use crate::{
+    functionality::{query_parts, transaction_functionality},
+    interceptor::{Call, Interceptor, Request, Response},
+    proto::{self, QueryHeader::QueryHeader},
+    query::response_header,
+    Status,
+};
+use failure::Error;
+use std::time::Duration;
+
+/// Transactions submitted, by `functionality`; retries are not counted.
+pub const TRANSACTIONS_SUBMITTED: &str = "hedera_transactions_submitted_total";
+
+/// Queries sent, by `functionality`; retries are not counted.
+pub const QUERIES_SENT: &str = "hedera_queries_sent_total";
+
+/// Responses from nodes, by `functionality` and precheck `status`.
+pub const PRECHECKS: &str = "hedera_prechecks_total";
+
+/// Requests sent again because the node was busy, by `functionality`.
+pub const RETRIES: &str = "hedera_retries_total";
+
+/// Tinybars paid to nodes for queries that were answered, by `functionality`.
+pub const QUERY_PAYMENTS: &str = "hedera_query_payments_tinybars_total";
+
+/// The time from sending a request to receiving the response, or failing, by `node`.
+pub const RPC_LATENCY: &str = "hedera_rpc_latency";
+
+/// Receives the metrics recorded by a client, to forward them to a metrics backend.
+///
+/// Each metric is labeled with some of `functionality`, the `HederaFunctionality` of the
+/// request; `status`, the precheck status of the response; and `node`, the account of the
+/// node the request was sent to.
+pub trait MetricsRecorder: Send + Sync {
+    /// Increase the counter `name` by `value`.
+    fn increment_counter(
+        &self,
+        name: &'static str,
+        value: u64,
+        labels: &[(&'static str, String)],
+    );
+
+    /// Record a sample of the histogram `name`.
+    fn record_histogram(
+        &self,
+        name: &'static str,
+        value: Duration,
+        labels: &[(&'static str, String)],
+    );
+}
+
+// Records the metrics of every request through the interceptor of a client
+pub(crate) struct Metrics<R>(pub(crate) R);
+
+impl<R: MetricsRecorder> Interceptor for Metrics<R> {
+    fn before_send(&self, call: &mut Call<'_>) -> Result<(), Error> {
+        let (name, functionality) = match call.request() {
+            Request::Query(query) => (QUERIES_SENT, query_parts(query).0),
+            Request::Transaction(tx) => (TRANSACTIONS_SUBMITTED, transaction_functionality(tx)),
+        };
+
+        let labels = [("functionality", format!("{:?}", functionality))];
+
+        if call.attempt() > 1 {
+            self.0.increment_counter(RETRIES, 1, &labels);
+        } else {
+            self.0.increment_counter(name, 1, &labels);
+        }
+
+        Ok(())
+    }
+
+    fn after_response(&self, call: &Call<'_>, response: Response<'_>, latency: Duration) {
+        let node = call.node().map_or_else(|| "unknown".to_owned(), |node| node.to_string());
+        self.0.record_histogram(RPC_LATENCY, latency, &[("node", node)]);
+
+        let (functionality, status) = match (call.request(), response) {
+            (Request::Query(query), Response::Query(response)) => {
+                let (functionality, header) = query_parts(query);
+                let status: Status = match response_header(response) {
+                    Some(header) => header.get_nodeTransactionPrecheckCode().into(),
+                    None => return,
+                };
+
+                if status == Status::Ok {
+                    let payment = header.map_or(0, query_payment);
+                    if payment > 0 {
+                        let labels = [("functionality", format!("{:?}", functionality))];
+                        self.0.increment_counter(QUERY_PAYMENTS, payment, &labels);
+                    }
+                }
+
+                (functionality, status)
+            }
+
+            (Request::Transaction(tx), Response::Transaction(response)) => (
+                transaction_functionality(tx),
+                response.get_nodeTransactionPrecheckCode().into(),
+            ),
+
+            _ => return,
+        };
+
+        self.0.increment_counter(
+            PRECHECKS,
+            1,
+            &[
+                ("functionality", format!("{:?}", functionality)),
+                ("status", format!("{:?}", status)),
+            ],
+        );
+    }
+}
+
+// The tinybars paid to the node by the payment attached to an answer to a query
+fn query_payment(header: &QueryHeader) -> u64 {
+    use self::proto::{
+        QueryHeader::ResponseType, TransactionBody::TransactionBody_oneof_data::cryptoTransfer,
+    };
+
+    if header.get_responseType() != ResponseType::ANSWER_ONLY {
+        return 0;
+    }
+
+    let body = header.get_payment().get_body();
+    let node = body.get_nodeAccountID();
+
+    match &body.data {
+        Some(cryptoTransfer(data)) => data
+            .get_transfers()
+            .get_accountAmounts()
+            .iter()
+            .filter(|transfer| transfer.get_accountID() == node && transfer.get_amount() > 0)
+            .map(|transfer| transfer.get_amount() as u64)
+            .sum(),
+
+        _ => 0,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Metrics, MetricsRecorder, PRECHECKS, RETRIES, TRANSACTIONS_SUBMITTED};
+    use crate::{
+        interceptor::{Call, Interceptor, Request, Response},
+        proto, AccountId,
+    };
+    use parking_lot::Mutex;
+    use std::time::Duration;
+
+    #[derive(Default)]
+    struct Recorder {
+        counters: Mutex<Vec<(&'static str, u64, Vec<(&'static str, String)>)>>,
+    }
+
+    impl MetricsRecorder for &'_ Recorder {
+        fn increment_counter(
+            &self,
+            name: &'static str,
+            value: u64,
+            labels: &[(&'static str, String)],
+        ) {
+            self.counters.lock().push((name, value, labels.to_vec()));
+        }
+
+        fn record_histogram(&self, _: &'static str, _: Duration, _: &[(&'static str, String)]) {}
+    }
+
+    #[test]
+    fn test_transaction_metrics() {
+        let recorder = Recorder::default();
+        let metrics = Metrics(&recorder);
+
+        let mut tx = proto::Transaction::Transaction::new();
+        tx.mut_body()
+            .set_cryptoTransfer(proto::CryptoTransfer::CryptoTransferTransactionBody::new());
+
+        let mut response = proto::TransactionResponse::TransactionResponse::new();
+        response.set_nodeTransactionPrecheckCode(proto::ResponseCode::ResponseCodeEnum::BUSY);
+
+        let node = Some(AccountId::new(0, 0, 3));
+
+        for attempt in 1..=2 {
+            let mut call = Call::new(Request::Transaction(&tx), node, attempt);
+            metrics.before_send(&mut call).unwrap();
+            let latency = Duration::from_millis(5);
+            metrics.after_response(&call, Response::Transaction(&response), latency);
+        }
+
+        let functionality = ("functionality", "CryptoTransfer".to_owned());
+        let counters = recorder.counters.lock();
+
+        assert_eq!(counters[0], (TRANSACTIONS_SUBMITTED, 1, vec![functionality.clone()]));
+        assert_eq!(
+            counters[1],
+            (PRECHECKS, 1, vec![functionality.clone(), ("status", "Busy".to_owned())])
+        );
+        assert_eq!(counters[2], (RETRIES, 1, vec![functionality]));
+    }
+}
@@ -0,0 +1,521 @@
+//! An in-process mock of a Hedera node, for testing code that uses the SDK without a network.
+//!
+//! The mock serves the crypto, file and smart contract services over plaintext gRPC on a
+//! local port, so a client built with `Client::new` for its address talks to it like any
+//! other node. Transactions are checked as a node would before submitting them, then applied
+//! to an in-memory ledger at once, so their receipts and records are available immediately.
+//!
+//! Accounts and files are simulated; smart contracts, claims and system transactions are
+//! answered with `NotSupported`.
+
+mod ledger;
+
+use self::ledger::{Ledger, QUERY_COST};
+use crate::{
+    proto::{
+        self,
+        CryptoService_grpc::{CryptoService, CryptoServiceServer},
+        FileService_grpc::{FileService, FileServiceServer},
+        Query::Query,
+        QueryHeader::QueryHeader,
+        ResponseCode::ResponseCodeEnum,
+        ResponseHeader::ResponseHeader,
+        SmartContractService_grpc::{SmartContractService, SmartContractServiceServer},
+        Transaction::Transaction,
+    },
+    AccountId, FileId, PublicKey,
+};
+use failure::{format_err, Error};
+use grpc::RequestOptions;
+use parking_lot::Mutex;
+use protobuf::RepeatedField;
+use std::{net::SocketAddr, sync::Arc};
+
+type TransactionResponse = grpc::SingleResponse<proto::TransactionResponse::TransactionResponse>;
+type QueryResponse = grpc::SingleResponse<proto::Response::Response>;
+
+/// A mock node running in this process. It stops when dropped.
+pub struct MockServer {
+    // Held to keep the server running
+    _server: grpc::Server,
+    address: SocketAddr,
+    node: AccountId,
+    ledger: Arc<Mutex<Ledger>>,
+}
+
+impl MockServer {
+    /// Start a mock node on a free port of `127.0.0.1`, with account `0.0.3`.
+    pub fn start() -> Result<Self, Error> {
+        let node = AccountId::new(0, 0, 3);
+        let ledger = Arc::new(Mutex::new(Ledger::new(node)));
+        let handler = Handler(ledger.clone());
+
+        let mut builder = grpc::ServerBuilder::new_plain();
+        builder.http.set_addr("127.0.0.1:0")?;
+        builder.add_service(CryptoServiceServer::new_service_def(handler.clone()));
+        builder.add_service(FileServiceServer::new_service_def(handler.clone()));
+        builder.add_service(SmartContractServiceServer::new_service_def(handler));
+
+        let server = builder.build()?;
+        let address = match server.local_addr() {
+            httpbis::AnySocketAddr::Inet(address) => *address,
+            address => Err(format_err!("mock server is not on a TCP socket: {}", address))?,
+        };
+
+        Ok(Self {
+            _server: server,
+            address,
+            node,
+            ledger,
+        })
+    }
+
+    /// The address the mock node serves gRPC on, to build a client with.
+    pub fn address(&self) -> SocketAddr {
+        self.address
+    }
+
+    /// The account of the mock node. Transactions and query payments must be sent to it.
+    pub fn node(&self) -> AccountId {
+        self.node
+    }
+
+    /// Create an account with `key` and a balance of `balance` tinybars, such as one to be
+    /// the operator of a client.
+    pub fn create_account(&self, key: PublicKey, balance: u64) -> AccountId {
+        self.ledger.lock().create_account(key, balance)
+    }
+
+    /// The balance of an account, in tinybars; `None` if there is no such account.
+    pub fn balance(&self, account: AccountId) -> Option<u64> {
+        self.ledger
+            .lock()
+            .accounts
+            .get(&account)
+            .map(|account| account.balance)
+    }
+
+    /// The contents of a file; `None` if there is no such file.
+    pub fn file_contents(&self, file: FileId) -> Option<Vec<u8>> {
+        self.ledger
+            .lock()
+            .files
+            .get(&file)
+            .map(|file| file.contents.clone())
+    }
+}
+
+#[derive(Clone)]
+struct Handler(Arc<Mutex<Ledger>>);
+
+impl Handler {
+    fn submit(&self, tx: Transaction) -> TransactionResponse {
+        let mut response = proto::TransactionResponse::TransactionResponse::new();
+        response.set_nodeTransactionPrecheckCode(self.0.lock().submit(&tx));
+
+        grpc::SingleResponse::completed(response)
+    }
+
+    fn query(&self, query: Query) -> QueryResponse {
+        grpc::SingleResponse::completed(answer(&mut self.0.lock(), query))
+    }
+}
+
+impl CryptoService for Handler {
+    fn create_account(&self, _: RequestOptions, p: Transaction) -> TransactionResponse {
+        self.submit(p)
+    }
+
+    fn update_account(&self, _: RequestOptions, p: Transaction) -> TransactionResponse {
+        self.submit(p)
+    }
+
+    fn crypto_transfer(&self, _: RequestOptions, p: Transaction) -> TransactionResponse {
+        self.submit(p)
+    }
+
+    fn crypto_delete(&self, _: RequestOptions, p: Transaction) -> TransactionResponse {
+        self.submit(p)
+    }
+
+    fn add_claim(&self, _: RequestOptions, p: Transaction) -> TransactionResponse {
+        self.submit(p)
+    }
+
+    fn delete_claim(&self, _: RequestOptions, p: Transaction) -> TransactionResponse {
+        self.submit(p)
+    }
+
+    fn get_claim(&self, _: RequestOptions, p: Query) -> QueryResponse {
+        self.query(p)
+    }
+
+    fn get_account_records(&self, _: RequestOptions, p: Query) -> QueryResponse {
+        self.query(p)
+    }
+
+    fn crypto_get_balance(&self, _: RequestOptions, p: Query) -> QueryResponse {
+        self.query(p)
+    }
+
+    fn get_account_info(&self, _: RequestOptions, p: Query) -> QueryResponse {
+        self.query(p)
+    }
+
+    fn get_transaction_receipts(&self, _: RequestOptions, p: Query) -> QueryResponse {
+        self.query(p)
+    }
+
+    fn get_fast_transaction_record(&self, _: RequestOptions, p: Query) -> QueryResponse {
+        self.query(p)
+    }
+
+    fn get_tx_record_by_tx_id(&self, _: RequestOptions, p: Query) -> QueryResponse {
+        self.query(p)
+    }
+
+    fn get_stakers_by_account_id(&self, _: RequestOptions, p: Query) -> QueryResponse {
+        self.query(p)
+    }
+}
+
+impl FileService for Handler {
+    fn create_file(&self, _: RequestOptions, p: Transaction) -> TransactionResponse {
+        self.submit(p)
+    }
+
+    fn update_file(&self, _: RequestOptions, p: Transaction) -> TransactionResponse {
+        self.submit(p)
+    }
+
+    fn delete_file(&self, _: RequestOptions, p: Transaction) -> TransactionResponse {
+        self.submit(p)
+    }
+
+    fn append_content(&self, _: RequestOptions, p: Transaction) -> TransactionResponse {
+        self.submit(p)
+    }
+
+    fn get_file_content(&self, _: RequestOptions, p: Query) -> QueryResponse {
+        self.query(p)
+    }
+
+    fn get_file_info(&self, _: RequestOptions, p: Query) -> QueryResponse {
+        self.query(p)
+    }
+
+    fn system_delete(&self, _: RequestOptions, p: Transaction) -> TransactionResponse {
+        self.submit(p)
+    }
+
+    fn system_undelete(&self, _: RequestOptions, p: Transaction) -> TransactionResponse {
+        self.submit(p)
+    }
+}
+
+impl SmartContractService for Handler {
+    fn create_contract(&self, _: RequestOptions, p: Transaction) -> TransactionResponse {
+        self.submit(p)
+    }
+
+    fn update_contract(&self, _: RequestOptions, p: Transaction) -> TransactionResponse {
+        self.submit(p)
+    }
+
+    fn contract_call_method(&self, _: RequestOptions, p: Transaction) -> TransactionResponse {
+        self.submit(p)
+    }
+
+    fn get_contract_info(&self, _: RequestOptions, p: Query) -> QueryResponse {
+        self.query(p)
+    }
+
+    fn contract_call_local_method(&self, _: RequestOptions, p: Query) -> QueryResponse {
+        self.query(p)
+    }
+
+    fn contract_get_bytecode(&self, _: RequestOptions, p: Query) -> QueryResponse {
+        self.query(p)
+    }
+
+    fn get_by_solidity_id(&self, _: RequestOptions, p: Query) -> QueryResponse {
+        self.query(p)
+    }
+
+    fn get_tx_record_by_contract_id(&self, _: RequestOptions, p: Query) -> QueryResponse {
+        self.query(p)
+    }
+
+    fn delete_contract(&self, _: RequestOptions, p: Transaction) -> TransactionResponse {
+        self.submit(p)
+    }
+
+    fn system_delete(&self, _: RequestOptions, p: Transaction) -> TransactionResponse {
+        self.submit(p)
+    }
+
+    fn system_undelete(&self, _: RequestOptions, p: Transaction) -> TransactionResponse {
+        self.submit(p)
+    }
+}
+
+// Check a query, take its payment and answer it. The response is always of the kind the
+// query asked for, with a precheck status in its header.
+fn answer(ledger: &mut Ledger, query: Query) -> proto::Response::Response {
+    use self::proto::Query::Query_oneof_query::*;
+
+    let mut response = proto::Response::Response::new();
+
+    match query.query {
+        Some(cryptogetAccountBalance(query)) => {
+            let id = query.get_accountID();
+            let (header, balance) = respond(ledger, query.get_header(), QUERY_COST, |ledger| {
+                Ok(ledger.account(id)?.balance)
+            });
+
+            let mut res = proto::CryptoGetAccountBalance::CryptoGetAccountBalanceResponse::new();
+            res.set_header(header);
+            res.set_accountID(id.clone());
+            res.set_balance(balance.unwrap_or_default());
+
+            response.set_cryptogetAccountBalance(res);
+        }
+
+        Some(cryptoGetInfo(query)) => {
+            let id = query.get_accountID();
+            let (header, info) = respond(ledger, query.get_header(), QUERY_COST, |ledger| {
+                ledger.account_info(id)
+            });
+
+            let mut res = proto::CryptoGetInfo::CryptoGetInfoResponse::new();
+            res.set_header(header);
+            if let Some(info) = info {
+                res.set_accountInfo(info);
+            }
+
+            response.set_cryptoGetInfo(res);
+        }
+
+        Some(cryptoGetAccountRecords(query)) => {
+            let id = query.get_accountID();
+            let (header, records) = respond(ledger, query.get_header(), QUERY_COST, |ledger| {
+                ledger.account_records(id)
+            });
+
+            let mut res = proto::CryptoGetAccountRecords::CryptoGetAccountRecordsResponse::new();
+            res.set_header(header);
+            res.set_accountID(id.clone());
+            res.set_records(RepeatedField::from_vec(records.unwrap_or_default()));
+
+            response.set_cryptoGetAccountRecords(res);
+        }
+
+        Some(fileGetContents(query)) => {
+            let id = query.get_fileID();
+            let (header, contents) = respond(ledger, query.get_header(), QUERY_COST, |ledger| {
+                match ledger.file(id)? {
+                    file if file.deleted => Err(ResponseCodeEnum::FILE_DELETED),
+                    file => Ok(file.contents.clone()),
+                }
+            });
+
+            let mut res = proto::FileGetContents::FileGetContentsResponse::new();
+            res.set_header(header);
+            if let Some(contents) = contents {
+                res.mut_fileContents().set_fileID(id.clone());
+                res.mut_fileContents().set_contents(contents);
+            }
+
+            response.set_fileGetContents(res);
+        }
+
+        Some(fileGetInfo(query)) => {
+            let id = query.get_fileID();
+            let (header, info) = respond(ledger, query.get_header(), QUERY_COST, |ledger| {
+                ledger.file_info(id)
+            });
+
+            let mut res = proto::FileGetInfo::FileGetInfoResponse::new();
+            res.set_header(header);
+            if let Some(info) = info {
+                res.set_fileInfo(info);
+            }
+
+            response.set_fileGetInfo(res);
+        }
+
+        // Receipts are free, as are the records that are kept briefly like receipts
+        Some(transactionGetReceipt(query)) => {
+            let id = query.get_transactionID();
+            let (header, receipt) = respond(ledger, query.get_header(), 0, |ledger| {
+                ledger
+                    .receipt(id)
+                    .cloned()
+                    .ok_or(ResponseCodeEnum::RECEIPT_NOT_FOUND)
+            });
+
+            let mut res = proto::TransactionGetReceipt::TransactionGetReceiptResponse::new();
+            res.set_header(header);
+            if let Some(receipt) = receipt {
+                res.set_receipt(receipt);
+            }
+
+            response.set_transactionGetReceipt(res);
+        }
+
+        Some(transactionGetFastRecord(query)) => {
+            let id = query.get_transactionID();
+            let (header, record) = respond(ledger, query.get_header(), 0, |ledger| {
+                ledger
+                    .record(id)
+                    .cloned()
+                    .ok_or(ResponseCodeEnum::RECORD_NOT_FOUND)
+            });
+
+            let mut res = proto::TransactionGetFastRecord::TransactionGetFastRecordResponse::new();
+            res.set_header(header);
+            if let Some(record) = record {
+                res.set_transactionRecord(record);
+            }
+
+            response.set_transactionGetFastRecord(res);
+        }
+
+        Some(transactionGetRecord(query)) => {
+            let id = query.get_transactionID();
+            let (header, record) = respond(ledger, query.get_header(), QUERY_COST, |ledger| {
+                ledger
+                    .record(id)
+                    .cloned()
+                    .ok_or(ResponseCodeEnum::RECORD_NOT_FOUND)
+            });
+
+            let mut res = proto::TransactionGetRecord::TransactionGetRecordResponse::new();
+            res.set_header(header);
+            if let Some(record) = record {
+                res.set_transactionRecord(record);
+            }
+
+            response.set_transactionGetRecord(res);
+        }
+
+        //////////////////////// NOT SIMULATED
+        Some(cryptoGetClaim(query)) => {
+            let mut res = proto::CryptoGetClaim::CryptoGetClaimResponse::new();
+            res.set_header(not_supported(query.get_header()));
+            response.set_cryptoGetClaim(res);
+        }
+
+        Some(cryptoGetProxyStakers(query)) => {
+            let mut res = proto::CryptoGetStakers::CryptoGetStakersResponse::new();
+            res.set_header(not_supported(query.get_header()));
+            response.set_cryptoGetProxyStakers(res);
+        }
+
+        Some(contractGetInfo(query)) => {
+            let mut res = proto::ContractGetInfo::ContractGetInfoResponse::new();
+            res.set_header(not_supported(query.get_header()));
+            response.set_contractGetInfo(res);
+        }
+
+        Some(contractGetBytecode(query)) => {
+            let mut res = proto::ContractGetBytecode::ContractGetBytecodeResponse::new();
+            res.set_header(not_supported(query.get_header()));
+            response.set_contractGetBytecodeResponse(res);
+        }
+
+        Some(contractCallLocal(query)) => {
+            let mut res = proto::ContractCallLocal::ContractCallLocalResponse::new();
+            res.set_header(not_supported(query.get_header()));
+            response.set_contractCallLocal(res);
+        }
+
+        Some(ContractGetRecords(query)) => {
+            let mut res = proto::ContractGetRecords::ContractGetRecordsResponse::new();
+            res.set_header(not_supported(query.get_header()));
+            response.set_contractGetRecordsResponse(res);
+        }
+
+        Some(getBySolidityID(query)) => {
+            let mut res = proto::GetBySolidityID::GetBySolidityIDResponse::new();
+            res.set_header(not_supported(query.get_header()));
+            response.set_getBySolidityID(res);
+        }
+
+        Some(getByKey(query)) => {
+            let mut res = proto::GetByKey::GetByKeyResponse::new();
+            res.set_header(not_supported(query.get_header()));
+            response.set_getByKey(res);
+        }
+
+        None => {}
+    }
+
+    response
+}
+
+// Answer a query once its header is checked and its payment went through. A query about
+// something that does not exist fails without being charged for.
+fn respond<T>(
+    ledger: &mut Ledger,
+    header: &QueryHeader,
+    cost: u64,
+    answer: impl Fn(&Ledger) -> Result<T, ResponseCodeEnum>,
+) -> (ResponseHeader, Option<T>) {
+    if let Err(status) = answer(ledger) {
+        let mut response = ResponseHeader::new();
+        response.set_responseType(header.get_responseType());
+        response.set_nodeTransactionPrecheckCode(status);
+
+        return (response, None);
+    }
+
+    let (response, answered) = ledger.answer(header, cost);
+
+    // Answered after the payment, so a query about the payer sees it
+    let value = if answered { answer(ledger).ok() } else { None };
+
+    (response, value)
+}
+
+fn not_supported(header: &QueryHeader) -> ResponseHeader {
+    let mut response = ResponseHeader::new();
+    response.set_responseType(header.get_responseType());
+    response.set_nodeTransactionPrecheckCode(ResponseCodeEnum::NOT_SUPPORTED);
+
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::MockServer;
+    use crate::{Client, SecretKey, Status};
+
+    #[test]
+    fn test_client_against_mock() -> Result<(), failure::Error> {
+        let mock = MockServer::start()?;
+
+        let (secret, _) = SecretKey::generate("");
+        let operator = mock.create_account(secret.public(), 1_000_000_000);
+        let (other, _) = SecretKey::generate("");
+        let receiver = mock.create_account(other.public(), 0);
+
+        let mut client = Client::new(mock.address().to_string())?;
+        client.set_node(mock.node());
+        client.set_operator(operator, move || secret.clone());
+
+        let id = client
+            .transfer_crypto()
+            .transfer(operator, -500)
+            .transfer(receiver, 500)
+            .execute()?;
+
+        let receipt = client.transaction(id).receipt().get()?;
+        assert_eq!(receipt.status, Status::Success);
+
+        assert_eq!(client.account(receiver).balance().get()?, 500);
+        assert_eq!(mock.balance(receiver), Some(500));
+
+        Ok(())
+    }
+}
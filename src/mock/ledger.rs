@@ -0,0 +1,829 @@
+use crate::{
+    crypto::{PublicKey, SecretKey, Signature},
+    proto::{
+        self,
+        BasicTypes::{AccountID, Key, KeyList},
+        QueryHeader::{QueryHeader, ResponseType},
+        ResponseCode::ResponseCodeEnum,
+        ResponseHeader::ResponseHeader,
+        ToProto,
+    },
+    AccountId, FileId,
+};
+use chrono::{DateTime, Utc};
+use protobuf::Message;
+use sha2::{Digest, Sha384};
+use std::{collections::HashMap, time::Duration};
+use try_from::TryInto;
+
+// The fee charged for every transaction, including the payments for queries
+pub(crate) const TRANSACTION_FEE: u64 = 100_000;
+
+// The cost of answering a query that is not free
+pub(crate) const QUERY_COST: u64 = 100_000;
+
+// The auto-renew period of new accounts, about 3 months
+const AUTO_RENEW_PERIOD: Duration = Duration::from_secs(7_890_000);
+
+// Transactions are identified by their payer and valid start
+type TransactionKey = (AccountId, i64, i32);
+
+pub(crate) struct Account {
+    pub(crate) key: PublicKey,
+    pub(crate) balance: u64,
+    deleted: bool,
+    receiver_signature_required: bool,
+    send_record_threshold: u64,
+    receive_record_threshold: u64,
+    auto_renew_period: Duration,
+    expiration_time: DateTime<Utc>,
+}
+
+pub(crate) struct File {
+    pub(crate) contents: Vec<u8>,
+    keys: Vec<PublicKey>,
+    pub(crate) deleted: bool,
+    expiration_time: DateTime<Utc>,
+}
+
+// The state of the mock network: accounts, files and the records of every transaction
+// that reached consensus
+pub(crate) struct Ledger {
+    node: AccountId,
+    pub(crate) accounts: HashMap<AccountId, Account>,
+    pub(crate) files: HashMap<FileId, File>,
+    records: HashMap<TransactionKey, proto::TransactionRecord::TransactionRecord>,
+    next_entity: i64,
+}
+
+impl Ledger {
+    pub(crate) fn new(node: AccountId) -> Self {
+        let mut ledger = Self {
+            node,
+            accounts: HashMap::new(),
+            files: HashMap::new(),
+            records: HashMap::new(),
+            next_entity: 1001,
+        };
+
+        // The node is paid fees, so it needs an account; nobody can sign for it
+        let (secret, _) = SecretKey::generate("");
+        ledger.accounts.insert(node, Account::new(secret.public(), 0));
+
+        ledger
+    }
+
+    pub(crate) fn create_account(&mut self, key: PublicKey, balance: u64) -> AccountId {
+        let id = AccountId::new(0, 0, self.next_entity());
+        self.accounts.insert(id, Account::new(key, balance));
+
+        id
+    }
+
+    fn next_entity(&mut self) -> i64 {
+        let entity = self.next_entity;
+        self.next_entity += 1;
+
+        entity
+    }
+
+    // Check a transaction as a node does before submitting it; if it passes, apply it as
+    // though it reached consensus and keep its record
+    pub(crate) fn submit(&mut self, tx: &proto::Transaction::Transaction) -> ResponseCodeEnum {
+        match self.precheck(tx) {
+            ResponseCodeEnum::OK => {}
+            status => return status,
+        }
+
+        let body = tx.get_body();
+        let payer: AccountId = body.get_transactionID().get_accountID().clone().into();
+        let signatures = signatures(tx);
+
+        // The fee is charged whether or not the transaction succeeds
+        let fee = TRANSACTION_FEE as i64;
+        let mut transfers = vec![(payer, -fee), (self.node, fee)];
+        self.transfer(&transfers);
+
+        let mut receipt = proto::TransactionReceipt::TransactionReceipt::new();
+        let status = self.apply(body, &signatures, &mut receipt, &mut transfers);
+        receipt.set_status(status);
+
+        let mut record = proto::TransactionRecord::TransactionRecord::new();
+        record.set_receipt(receipt);
+        record.set_transactionHash(Sha384::digest(&tx.write_to_bytes().unwrap()).to_vec());
+        record.set_consensusTimestamp(Utc::now().to_proto().unwrap());
+        record.set_transactionID(body.get_transactionID().clone());
+        record.set_memo(body.get_memo().to_owned());
+        record.set_transactionFee(TRANSACTION_FEE);
+        record.set_transferList(transfer_list(&transfers));
+
+        self.records.insert(transaction_key(body.get_transactionID()), record);
+
+        ResponseCodeEnum::OK
+    }
+
+    fn precheck(&self, tx: &proto::Transaction::Transaction) -> ResponseCodeEnum {
+        use self::proto::TransactionBody::TransactionBody_oneof_data::*;
+
+        if !tx.has_body() {
+            return ResponseCodeEnum::INVALID_TRANSACTION_BODY;
+        }
+
+        let body = tx.get_body();
+
+        if AccountId::from(body.get_nodeAccountID().clone()) != self.node {
+            return ResponseCodeEnum::INVALID_NODE_ACCOUNT;
+        }
+
+        if !body.has_transactionID() || !body.get_transactionID().has_accountID() {
+            return ResponseCodeEnum::INVALID_TRANSACTION_ID;
+        }
+
+        let id = body.get_transactionID();
+        let valid_start: DateTime<Utc> = id.get_transactionValidStart().clone().into();
+        let valid_duration =
+            chrono::Duration::seconds(body.get_transactionValidDuration().get_seconds());
+
+        if valid_duration <= chrono::Duration::zero() {
+            return ResponseCodeEnum::INVALID_TRANSACTION_DURATION;
+        }
+
+        let now = Utc::now();
+        if valid_start > now {
+            return ResponseCodeEnum::INVALID_TRANSACTION_START;
+        }
+
+        if valid_start + valid_duration < now {
+            return ResponseCodeEnum::TRANSACTION_EXPIRED;
+        }
+
+        if body.get_memo().len() > 100 {
+            return ResponseCodeEnum::MEMO_TOO_LONG;
+        }
+
+        if self.records.contains_key(&transaction_key(id)) {
+            return ResponseCodeEnum::DUPLICATE_TRANSACTION;
+        }
+
+        let payer = match self.accounts.get(&id.get_accountID().clone().into()) {
+            Some(account) if !account.deleted => account,
+            _ => return ResponseCodeEnum::PAYER_ACCOUNT_NOT_FOUND,
+        };
+
+        if !signed_by(&tx.get_body().write_to_bytes().unwrap(), &signatures(tx), &payer.key) {
+            return ResponseCodeEnum::INVALID_SIGNATURE;
+        }
+
+        if body.get_transactionFee() < TRANSACTION_FEE {
+            return ResponseCodeEnum::INSUFFICIENT_TX_FEE;
+        }
+
+        if payer.balance < TRANSACTION_FEE {
+            return ResponseCodeEnum::INSUFFICIENT_PAYER_BALANCE;
+        }
+
+        match &body.data {
+            Some(cryptoCreateAccount(_))
+            | Some(cryptoTransfer(_))
+            | Some(cryptoUpdateAccount(_))
+            | Some(cryptoDelete(_))
+            | Some(fileCreate(_))
+            | Some(fileAppend(_))
+            | Some(fileUpdate(_))
+            | Some(fileDelete(_)) => ResponseCodeEnum::OK,
+
+            None => ResponseCodeEnum::EMPTY_TRANSACTION_BODY,
+
+            // Contracts, claims and system transactions are not simulated
+            _ => ResponseCodeEnum::NOT_SUPPORTED,
+        }
+    }
+
+    // Apply the effect of a transaction that passed precheck, returning the status
+    // for its receipt
+    fn apply(
+        &mut self,
+        body: &proto::TransactionBody::TransactionBody,
+        signatures: &[(Vec<u8>, Signature)],
+        receipt: &mut proto::TransactionReceipt::TransactionReceipt,
+        transfers: &mut Vec<(AccountId, i64)>,
+    ) -> ResponseCodeEnum {
+        use self::proto::TransactionBody::TransactionBody_oneof_data::*;
+
+        let message = body.write_to_bytes().unwrap();
+        let signed = |key: &PublicKey| signed_by(&message, signatures, key);
+        let payer: AccountId = body.get_transactionID().get_accountID().clone().into();
+
+        match &body.data {
+            Some(cryptoCreateAccount(data)) => {
+                if !data.has_key() {
+                    return ResponseCodeEnum::KEY_REQUIRED;
+                }
+
+                let key: PublicKey = match data.get_key().clone().try_into() {
+                    Ok(key) => key,
+                    Err(_) => return ResponseCodeEnum::BAD_ENCODING,
+                };
+
+                let initial_balance = data.get_initialBalance();
+                if self.accounts[&payer].balance < initial_balance {
+                    return ResponseCodeEnum::INSUFFICIENT_PAYER_BALANCE;
+                }
+
+                let id = self.create_account(key, 0);
+                let account = self.accounts.get_mut(&id).unwrap();
+                account.receiver_signature_required = data.get_receiverSigRequired();
+                account.send_record_threshold = data.get_sendRecordThreshold();
+                account.receive_record_threshold = data.get_receiveRecordThreshold();
+
+                let created = [(payer, -(initial_balance as i64)), (id, initial_balance as i64)];
+                self.transfer(&created);
+                transfers.extend_from_slice(&created);
+
+                receipt.set_accountID(id.to_proto().unwrap());
+            }
+
+            Some(cryptoTransfer(data)) => {
+                let mut amounts: Vec<(AccountId, i64)> = Vec::new();
+
+                for amount in data.get_transfers().get_accountAmounts() {
+                    let id: AccountId = amount.get_accountID().clone().into();
+                    if amounts.iter().any(|(other, _)| *other == id) {
+                        return ResponseCodeEnum::ACCOUNT_REPEATED_IN_ACCOUNT_AMOUNTS;
+                    }
+
+                    amounts.push((id, amount.get_amount()));
+                }
+
+                if amounts.iter().map(|(_, amount)| amount).sum::<i64>() != 0 {
+                    return ResponseCodeEnum::INVALID_ACCOUNT_AMOUNTS;
+                }
+
+                for (id, amount) in &amounts {
+                    let account = match self.accounts.get(id) {
+                        Some(account) if !account.deleted => account,
+                        Some(_) => return ResponseCodeEnum::ACCOUNT_DELETED,
+                        None => return ResponseCodeEnum::INVALID_ACCOUNT_ID,
+                    };
+
+                    let sending = *amount < 0;
+                    if (sending || account.receiver_signature_required) && !signed(&account.key) {
+                        return ResponseCodeEnum::INVALID_SIGNATURE;
+                    }
+
+                    if sending && account.balance < amount.abs() as u64 {
+                        return ResponseCodeEnum::INSUFFICIENT_ACCOUNT_BALANCE;
+                    }
+                }
+
+                self.transfer(&amounts);
+                transfers.extend_from_slice(&amounts);
+            }
+
+            Some(cryptoUpdateAccount(data)) => {
+                let id: AccountId = data.get_accountIDToUpdate().clone().into();
+                let account = match self.accounts.get_mut(&id) {
+                    Some(account) => account,
+                    None => return ResponseCodeEnum::INVALID_ACCOUNT_ID,
+                };
+
+                if account.deleted {
+                    return ResponseCodeEnum::ACCOUNT_DELETED;
+                }
+
+                if !signed(&account.key) {
+                    return ResponseCodeEnum::INVALID_SIGNATURE;
+                }
+
+                if data.has_key() {
+                    let key: Result<PublicKey, _> = data.get_key().clone().try_into();
+                    match key {
+                        // The new key must sign as well, so an account cannot be lost to
+                        // a key nobody holds
+                        Ok(key) => {
+                            if !signed(&key) {
+                                return ResponseCodeEnum::INVALID_SIGNATURE;
+                            }
+
+                            account.key = key;
+                        }
+
+                        Err(_) => return ResponseCodeEnum::BAD_ENCODING,
+                    }
+                }
+
+                if data.has_autoRenewPeriod() {
+                    let seconds = data.get_autoRenewPeriod().get_seconds();
+                    if seconds <= 0 {
+                        return ResponseCodeEnum::INVALID_RENEWAL_PERIOD;
+                    }
+
+                    account.auto_renew_period = Duration::from_secs(seconds as u64);
+                }
+
+                if data.has_expirationTime() {
+                    let expiration_time = data.get_expirationTime().clone().into();
+                    if expiration_time < account.expiration_time {
+                        return ResponseCodeEnum::INVALID_EXPIRATION_TIME;
+                    }
+
+                    account.expiration_time = expiration_time;
+                }
+            }
+
+            Some(cryptoDelete(data)) => {
+                let id: AccountId = data.get_deleteAccountID().clone().into();
+                let obtainer: AccountId = data.get_transferAccountID().clone().into();
+
+                let balance = match self.accounts.get(&id) {
+                    Some(account) if account.deleted => return ResponseCodeEnum::ACCOUNT_DELETED,
+                    Some(account) if !signed(&account.key) => {
+                        return ResponseCodeEnum::INVALID_SIGNATURE;
+                    }
+                    Some(account) => account.balance,
+                    None => return ResponseCodeEnum::INVALID_ACCOUNT_ID,
+                };
+
+                match self.accounts.get(&obtainer) {
+                    Some(account) if !account.deleted && obtainer != id => {}
+                    _ => return ResponseCodeEnum::OBTAINER_DOES_NOT_EXIST,
+                }
+
+                let moved = [(id, -(balance as i64)), (obtainer, balance as i64)];
+                self.transfer(&moved);
+                transfers.extend_from_slice(&moved);
+
+                self.accounts.get_mut(&id).unwrap().deleted = true;
+            }
+
+            Some(fileCreate(data)) => {
+                let keys = match keys(data.get_keys()) {
+                    Some(keys) => keys,
+                    None => return ResponseCodeEnum::BAD_ENCODING,
+                };
+
+                if keys.is_empty() {
+                    return ResponseCodeEnum::NO_WACL_KEY;
+                }
+
+                if !keys.iter().all(signed) {
+                    return ResponseCodeEnum::INVALID_SIGNATURE;
+                }
+
+                let expiration_time = if data.has_expirationTime() {
+                    data.get_expirationTime().clone().into()
+                } else {
+                    Utc::now() + chrono::Duration::from_std(AUTO_RENEW_PERIOD).unwrap()
+                };
+
+                let id = FileId::new(0, 0, self.next_entity());
+                self.files.insert(
+                    id,
+                    File {
+                        contents: data.get_contents().to_vec(),
+                        keys,
+                        deleted: false,
+                        expiration_time,
+                    },
+                );
+
+                receipt.set_fileID(id.to_proto().unwrap());
+            }
+
+            Some(fileAppend(data)) => {
+                let file = match self.writable_file(data.get_fileID(), &signed) {
+                    Ok(file) => file,
+                    Err(status) => return status,
+                };
+
+                file.contents.extend_from_slice(data.get_contents());
+            }
+
+            Some(fileUpdate(data)) => {
+                let new_keys = if data.has_keys() {
+                    match keys(data.get_keys()) {
+                        Some(keys) => {
+                            if keys.is_empty() {
+                                return ResponseCodeEnum::NO_WACL_KEY;
+                            }
+
+                            Some(keys)
+                        }
+
+                        None => return ResponseCodeEnum::BAD_ENCODING,
+                    }
+                } else {
+                    None
+                };
+
+                let file = match self.writable_file(data.get_fileID(), &signed) {
+                    Ok(file) => file,
+                    Err(status) => return status,
+                };
+
+                if !data.get_contents().is_empty() {
+                    file.contents = data.get_contents().to_vec();
+                }
+
+                if let Some(keys) = new_keys {
+                    file.keys = keys;
+                }
+
+                if data.has_expirationTime() {
+                    file.expiration_time = data.get_expirationTime().clone().into();
+                }
+            }
+
+            Some(fileDelete(data)) => {
+                let file = match self.writable_file(data.get_fileID(), &signed) {
+                    Ok(file) => file,
+                    Err(status) => return status,
+                };
+
+                file.deleted = true;
+                file.contents.clear();
+            }
+
+            // Anything else is rejected by precheck
+            _ => return ResponseCodeEnum::NOT_SUPPORTED,
+        }
+
+        ResponseCodeEnum::SUCCESS
+    }
+
+    // A file that exists, is not deleted and every key of which signed the transaction
+    fn writable_file(
+        &mut self,
+        id: &proto::BasicTypes::FileID,
+        signed: &dyn Fn(&PublicKey) -> bool,
+    ) -> Result<&mut File, ResponseCodeEnum> {
+        let file = self
+            .files
+            .get_mut(&id.clone().into())
+            .ok_or(ResponseCodeEnum::INVALID_FILE_ID)?;
+
+        if file.deleted {
+            return Err(ResponseCodeEnum::FILE_DELETED);
+        }
+
+        if !file.keys.iter().all(signed) {
+            return Err(ResponseCodeEnum::INVALID_SIGNATURE);
+        }
+
+        Ok(file)
+    }
+
+    // Move balances between accounts; the amounts must already be checked
+    fn transfer(&mut self, amounts: &[(AccountId, i64)]) {
+        for (id, amount) in amounts {
+            let account = self.accounts.get_mut(id).unwrap();
+            account.balance = (account.balance as i64 + amount) as u64;
+        }
+    }
+
+    // Check the header of a query and take its payment. Returns the header of the response
+    // and whether the answer should be included in it.
+    pub(crate) fn answer(&mut self, header: &QueryHeader, cost: u64) -> (ResponseHeader, bool) {
+        let mut response = ResponseHeader::new();
+        response.set_responseType(header.get_responseType());
+        response.set_cost(cost);
+
+        let status = match header.get_responseType() {
+            ResponseType::COST_ANSWER => ResponseCodeEnum::OK,
+            ResponseType::ANSWER_ONLY if cost == 0 => ResponseCodeEnum::OK,
+            ResponseType::ANSWER_ONLY => self.pay(header, cost),
+
+            // State proofs are not simulated
+            _ => ResponseCodeEnum::NOT_SUPPORTED,
+        };
+
+        response.set_nodeTransactionPrecheckCode(status);
+
+        let answer = status == ResponseCodeEnum::OK
+            && header.get_responseType() == ResponseType::ANSWER_ONLY;
+
+        (response, answer)
+    }
+
+    // Submit the payment for a query, which must transfer at least the cost to the node
+    fn pay(&mut self, header: &QueryHeader, cost: u64) -> ResponseCodeEnum {
+        use self::proto::TransactionBody::TransactionBody_oneof_data::cryptoTransfer;
+
+        if !header.has_payment() {
+            return ResponseCodeEnum::INSUFFICIENT_TX_FEE;
+        }
+
+        let payment = header.get_payment();
+        let node = self.node.to_proto().unwrap();
+
+        let paid: i64 = match &payment.get_body().data {
+            Some(cryptoTransfer(data)) => data
+                .get_transfers()
+                .get_accountAmounts()
+                .iter()
+                .filter(|amount| amount.get_accountID() == &node)
+                .map(|amount| amount.get_amount())
+                .sum(),
+
+            _ => return ResponseCodeEnum::INVALID_TRANSACTION_BODY,
+        };
+
+        if paid < cost as i64 {
+            return ResponseCodeEnum::INSUFFICIENT_TX_FEE;
+        }
+
+        match self.submit(payment) {
+            ResponseCodeEnum::OK => {}
+            status => return status,
+        }
+
+        // A payment that does not go through means the query is not answered
+        match self.receipt(payment.get_body().get_transactionID()) {
+            Some(receipt) if receipt.get_status() == ResponseCodeEnum::SUCCESS => {
+                ResponseCodeEnum::OK
+            }
+
+            Some(receipt) => receipt.get_status(),
+            None => ResponseCodeEnum::INVALID_TRANSACTION,
+        }
+    }
+
+    pub(crate) fn account_info(
+        &self,
+        id: &AccountID,
+    ) -> Result<proto::CryptoGetInfo::CryptoGetInfoResponse_AccountInfo, ResponseCodeEnum> {
+        let account = self.account(id)?;
+        let id: AccountId = id.clone().into();
+
+        let mut info = proto::CryptoGetInfo::CryptoGetInfoResponse_AccountInfo::new();
+        info.set_accountID(id.to_proto().unwrap());
+        info.set_contractAccountID(format!("{:040x}", id.account));
+        info.set_deleted(account.deleted);
+        info.set_key(account.key.to_proto().unwrap());
+        info.set_balance(account.balance);
+        info.set_generateSendRecordThreshold(account.send_record_threshold);
+        info.set_generateReceiveRecordThreshold(account.receive_record_threshold);
+        info.set_receiverSigRequired(account.receiver_signature_required);
+        info.set_expirationTime(account.expiration_time.to_proto().unwrap());
+        info.set_autoRenewPeriod(account.auto_renew_period.to_proto().unwrap());
+
+        Ok(info)
+    }
+
+    pub(crate) fn account(&self, id: &AccountID) -> Result<&Account, ResponseCodeEnum> {
+        match self.accounts.get(&id.clone().into()) {
+            Some(account) if account.deleted => Err(ResponseCodeEnum::ACCOUNT_DELETED),
+            Some(account) => Ok(account),
+            None => Err(ResponseCodeEnum::INVALID_ACCOUNT_ID),
+        }
+    }
+
+    pub(crate) fn account_records(
+        &self,
+        id: &AccountID,
+    ) -> Result<Vec<proto::TransactionRecord::TransactionRecord>, ResponseCodeEnum> {
+        self.account(id)?;
+
+        let mut records: Vec<_> = self
+            .records
+            .values()
+            .filter(|record| record.get_transactionID().get_accountID() == id)
+            .cloned()
+            .collect();
+
+        records.sort_by_key(|record| {
+            let timestamp = record.get_consensusTimestamp();
+            (timestamp.get_seconds(), timestamp.get_nanos())
+        });
+
+        Ok(records)
+    }
+
+    pub(crate) fn file(&self, id: &proto::BasicTypes::FileID) -> Result<&File, ResponseCodeEnum> {
+        self.files
+            .get(&id.clone().into())
+            .ok_or(ResponseCodeEnum::INVALID_FILE_ID)
+    }
+
+    pub(crate) fn file_info(
+        &self,
+        id: &proto::BasicTypes::FileID,
+    ) -> Result<proto::FileGetInfo::FileGetInfoResponse_FileInfo, ResponseCodeEnum> {
+        let file = self.file(id)?;
+
+        let mut keys = KeyList::new();
+        for key in &file.keys {
+            keys.mut_keys().push(key.to_proto().unwrap());
+        }
+
+        let mut info = proto::FileGetInfo::FileGetInfoResponse_FileInfo::new();
+        info.set_fileID(id.clone());
+        info.set_size(file.contents.len() as i64);
+        info.set_expirationTime(file.expiration_time.to_proto().unwrap());
+        info.set_deleted(file.deleted);
+        info.set_keys(keys);
+
+        Ok(info)
+    }
+
+    pub(crate) fn receipt(
+        &self,
+        id: &proto::BasicTypes::TransactionID,
+    ) -> Option<&proto::TransactionReceipt::TransactionReceipt> {
+        self.records
+            .get(&transaction_key(id))
+            .map(|record| record.get_receipt())
+    }
+
+    pub(crate) fn record(
+        &self,
+        id: &proto::BasicTypes::TransactionID,
+    ) -> Option<&proto::TransactionRecord::TransactionRecord> {
+        self.records.get(&transaction_key(id))
+    }
+}
+
+impl Account {
+    fn new(key: PublicKey, balance: u64) -> Self {
+        Self {
+            key,
+            balance,
+            deleted: false,
+            receiver_signature_required: false,
+            send_record_threshold: u64::max_value(),
+            receive_record_threshold: u64::max_value(),
+            auto_renew_period: AUTO_RENEW_PERIOD,
+            expiration_time: Utc::now() + chrono::Duration::from_std(AUTO_RENEW_PERIOD).unwrap(),
+        }
+    }
+}
+
+fn transaction_key(id: &proto::BasicTypes::TransactionID) -> TransactionKey {
+    let valid_start = id.get_transactionValidStart();
+
+    (
+        id.get_accountID().clone().into(),
+        valid_start.get_seconds(),
+        valid_start.get_nanos(),
+    )
+}
+
+fn transfer_list(transfers: &[(AccountId, i64)]) -> proto::CryptoTransfer::TransferList {
+    let mut list = proto::CryptoTransfer::TransferList::new();
+
+    for (id, amount) in transfers {
+        if *amount == 0 {
+            continue;
+        }
+
+        let mut account_amount = proto::CryptoTransfer::AccountAmount::new();
+        account_amount.set_accountID(id.to_proto().unwrap());
+        account_amount.set_amount(*amount);
+
+        list.mut_accountAmounts().push(account_amount);
+    }
+
+    list
+}
+
+fn keys(list: &KeyList) -> Option<Vec<PublicKey>> {
+    list.get_keys()
+        .iter()
+        .map(|key: &Key| TryInto::<PublicKey>::try_into(key.clone()).ok())
+        .collect()
+}
+
+// Every ed25519 signature on a transaction, however it is nested; with the public key
+// prefix it was given for, if any
+fn signatures(tx: &proto::Transaction::Transaction) -> Vec<(Vec<u8>, Signature)> {
+    fn collect(signature: &proto::BasicTypes::Signature, out: &mut Vec<(Vec<u8>, Signature)>) {
+        if signature.has_ed25519() {
+            if let Ok(parsed) = Signature::from_bytes(signature.get_ed25519()) {
+                out.push((Vec::new(), parsed));
+            }
+        } else if signature.has_signatureList() {
+            for signature in signature.get_signatureList().get_sigs() {
+                collect(signature, out);
+            }
+        } else if signature.has_thresholdSignature() {
+            for signature in signature.get_thresholdSignature().get_sigs().get_sigs() {
+                collect(signature, out);
+            }
+        }
+    }
+
+    let mut out = Vec::new();
+
+    for signature in tx.get_sigs().get_sigs() {
+        collect(signature, &mut out);
+    }
+
+    for pair in tx.get_sigMap().get_sigPair() {
+        if let Ok(parsed) = Signature::from_bytes(pair.get_ed25519()) {
+            out.push((pair.get_pubKeyPrefix().to_vec(), parsed));
+        }
+    }
+
+    out
+}
+
+fn signed_by(message: &[u8], signatures: &[(Vec<u8>, Signature)], key: &PublicKey) -> bool {
+    signatures.iter().any(|(prefix, signature)| {
+        key.as_bytes().starts_with(prefix) && key.verify(message, signature).unwrap_or(false)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Ledger, TRANSACTION_FEE};
+    use crate::{
+        proto::{self, ResponseCode::ResponseCodeEnum, ToProto},
+        AccountId, SecretKey, TransactionId,
+    };
+    use protobuf::Message;
+    use std::time::Duration;
+
+    fn transfer(
+        node: AccountId,
+        payer: AccountId,
+        secret: &SecretKey,
+        to: AccountId,
+        amount: i64,
+    ) -> proto::Transaction::Transaction {
+        let mut from_amount = proto::CryptoTransfer::AccountAmount::new();
+        from_amount.set_accountID(payer.to_proto().unwrap());
+        from_amount.set_amount(-amount);
+
+        let mut to_amount = proto::CryptoTransfer::AccountAmount::new();
+        to_amount.set_accountID(to.to_proto().unwrap());
+        to_amount.set_amount(amount);
+
+        let mut data = proto::CryptoTransfer::CryptoTransferTransactionBody::new();
+        data.mut_transfers().mut_accountAmounts().push(from_amount);
+        data.mut_transfers().mut_accountAmounts().push(to_amount);
+
+        let mut body = proto::TransactionBody::TransactionBody::new();
+        body.set_nodeAccountID(node.to_proto().unwrap());
+        body.set_transactionID(TransactionId::new(payer).to_proto().unwrap());
+        body.set_transactionValidDuration(Duration::from_secs(120).to_proto().unwrap());
+        body.set_transactionFee(TRANSACTION_FEE);
+        body.set_cryptoTransfer(data);
+
+        let signature = secret.sign(&body.write_to_bytes().unwrap());
+
+        let mut tx = proto::Transaction::Transaction::new();
+        tx.set_body(body);
+        tx.mut_sigs().mut_sigs().push(signature.to_proto().unwrap());
+
+        tx
+    }
+
+    #[test]
+    fn test_transfer() {
+        let node = AccountId::new(0, 0, 3);
+        let mut ledger = Ledger::new(node);
+
+        let (secret, _) = SecretKey::generate("");
+        let payer = ledger.create_account(secret.public(), 1_000_000);
+        let (other, _) = SecretKey::generate("");
+        let receiver = ledger.create_account(other.public(), 0);
+
+        let tx = transfer(node, payer, &secret, receiver, 500);
+        assert_eq!(ledger.submit(&tx), ResponseCodeEnum::OK);
+        assert_eq!(ledger.submit(&tx), ResponseCodeEnum::DUPLICATE_TRANSACTION);
+
+        let receipt = ledger.receipt(tx.get_body().get_transactionID()).unwrap();
+        assert_eq!(receipt.get_status(), ResponseCodeEnum::SUCCESS);
+
+        assert_eq!(ledger.accounts[&payer].balance, 1_000_000 - 500 - TRANSACTION_FEE);
+        assert_eq!(ledger.accounts[&receiver].balance, 500);
+        assert_eq!(ledger.accounts[&node].balance, TRANSACTION_FEE);
+    }
+
+    #[test]
+    fn test_precheck() {
+        let node = AccountId::new(0, 0, 3);
+        let mut ledger = Ledger::new(node);
+
+        let (secret, _) = SecretKey::generate("");
+        let payer = ledger.create_account(secret.public(), 1_000_000);
+        let (other, _) = SecretKey::generate("");
+
+        let tx = transfer(node, payer, &other, node, 500);
+        assert_eq!(ledger.submit(&tx), ResponseCodeEnum::INVALID_SIGNATURE);
+
+        let tx = transfer(AccountId::new(0, 0, 4), payer, &secret, node, 500);
+        assert_eq!(ledger.submit(&tx), ResponseCodeEnum::INVALID_NODE_ACCOUNT);
+
+        let tx = transfer(node, AccountId::new(0, 0, 5000), &secret, node, 500);
+        assert_eq!(ledger.submit(&tx), ResponseCodeEnum::PAYER_ACCOUNT_NOT_FOUND);
+
+        // Failing after precheck still charges the fee
+        let tx = transfer(node, payer, &secret, node, 2_000_000);
+        assert_eq!(ledger.submit(&tx), ResponseCodeEnum::OK);
+
+        let receipt = ledger.receipt(tx.get_body().get_transactionID()).unwrap();
+        assert_eq!(receipt.get_status(), ResponseCodeEnum::INSUFFICIENT_ACCOUNT_BALANCE);
+        assert_eq!(ledger.accounts[&payer].balance, 1_000_000 - TRANSACTION_FEE);
+    }
+}
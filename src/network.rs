@@ -0,0 +1,480 @@
+use crate::{
+    health::{HealthTracker, NodeHealth},
+    transport::Transport,
+    AccountId, ErrorKind, Status,
+};
+use failure::{format_err, Error};
+use std::{
+    collections::HashMap,
+    fmt,
+    net::{IpAddr, SocketAddr},
+    str::FromStr,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+    time::Instant,
+};
+use try_from::TryFrom;
+
+// The ports nodes serve gRPC on, in plaintext and over TLS, when an address does not give one
+pub(crate) const DEFAULT_PORT: u16 = 50211;
+const DEFAULT_TLS_PORT: u16 = 50212;
+
+/// The network address of a node.
+///
+/// Can be parsed from `host:port`, `[ipv6]:port` or a URI such as `grpc://host:port`. An
+/// `https://` URI asks for a TLS connection to the node, whatever the TLS settings of the
+/// client. Without a port, the default gRPC port of nodes is used: 50211, or 50212 for TLS.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Endpoint {
+    pub host: String,
+    pub port: u16,
+    pub tls: bool,
+}
+
+impl Endpoint {
+    pub fn new(host: impl Into<String>, port: u16) -> Self {
+        Self {
+            host: host.into(),
+            port,
+            tls: false,
+        }
+    }
+}
+
+impl fmt::Display for Endpoint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.tls {
+            write!(f, "https://")?;
+        }
+
+        if self.host.contains(':') {
+            write!(f, "[{}]:{}", self.host, self.port)
+        } else {
+            write!(f, "{}:{}", self.host, self.port)
+        }
+    }
+}
+
+impl FromStr for Endpoint {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let parse_error = || format_err!("failed to parse 'host:port' from address: {:?}", s);
+
+        // Only `https` asks for TLS; the path of a URI is dropped
+        let (tls, address) = match s.find("://") {
+            Some(index) => match &s[..index] {
+                "https" => (true, &s[index + 3..]),
+                "http" | "grpc" => (false, &s[index + 3..]),
+                scheme => Err(format_err!("unsupported scheme {:?} in address {:?}", scheme, s))?,
+            },
+
+            None => (false, s),
+        };
+
+        let address = match address.find('/') {
+            Some(index) => &address[..index],
+            None => address,
+        };
+
+        let (host, port) = if address.starts_with('[') {
+            // [ipv6]:port
+            let end = address.find(']').ok_or_else(parse_error)?;
+            let rest = &address[end + 1..];
+
+            let port = if rest.is_empty() {
+                None
+            } else if rest.starts_with(':') {
+                Some(&rest[1..])
+            } else {
+                return Err(parse_error());
+            };
+
+            (&address[1..end], port)
+        } else if address.parse::<IpAddr>().is_ok() {
+            // A bare IP address, which includes IPv6 addresses without brackets
+            (address, None)
+        } else {
+            match address.rfind(':') {
+                Some(index) => (&address[..index], Some(&address[index + 1..])),
+                None => (address, None),
+            }
+        };
+
+        if host.is_empty() {
+            return Err(parse_error());
+        }
+
+        let port = match port {
+            Some(port) => port.parse().map_err(|_| parse_error())?,
+            None if tls => DEFAULT_TLS_PORT,
+            None => DEFAULT_PORT,
+        };
+
+        Ok(Self {
+            host: host.to_owned(),
+            port,
+            tls,
+        })
+    }
+}
+
+impl TryFrom<Endpoint> for Endpoint {
+    type Err = Error;
+
+    fn try_from(address: Endpoint) -> Result<Self, Error> {
+        Ok(address)
+    }
+}
+
+impl TryFrom<String> for Endpoint {
+    type Err = Error;
+
+    fn try_from(address: String) -> Result<Self, Error> {
+        address.parse()
+    }
+}
+
+impl TryFrom<SocketAddr> for Endpoint {
+    type Err = Error;
+
+    fn try_from(address: SocketAddr) -> Result<Self, Error> {
+        Ok(Self::new(address.ip().to_string(), address.port()))
+    }
+}
+
+impl<S: Into<String>> TryFrom<(S, u16)> for Endpoint {
+    type Err = Error;
+
+    fn try_from((host, port): (S, u16)) -> Result<Self, Error> {
+        Ok(Self::new(host, port))
+    }
+}
+
+/// A known Hedera network, with the address and account of each of its nodes.
+#[derive(Debug, Clone)]
+pub enum NetworkPreset {
+    Mainnet,
+    Testnet,
+    Custom(Vec<(Endpoint, AccountId)>),
+}
+
+impl NetworkPreset {
+    pub fn nodes(&self) -> Vec<(Endpoint, AccountId)> {
+        let nodes: &[(&str, i64)] = match self {
+            NetworkPreset::Mainnet => &[
+                ("35.237.200.180", 3),
+                ("35.186.191.247", 4),
+                ("35.192.2.25", 5),
+                ("35.199.161.108", 6),
+                ("35.203.82.240", 7),
+                ("35.236.5.219", 8),
+                ("35.197.192.225", 9),
+                ("35.242.233.154", 10),
+                ("35.240.118.96", 11),
+                ("35.204.86.32", 12),
+            ],
+
+            NetworkPreset::Testnet => &[
+                ("0.testnet.hedera.com", 3),
+                ("1.testnet.hedera.com", 4),
+                ("2.testnet.hedera.com", 5),
+                ("3.testnet.hedera.com", 6),
+            ],
+
+            NetworkPreset::Custom(nodes) => return nodes.clone(),
+        };
+
+        nodes
+            .iter()
+            .map(|(host, account)| {
+                (
+                    Endpoint::new(*host, DEFAULT_PORT),
+                    AccountId::new(0, 0, *account),
+                )
+            })
+            .collect()
+    }
+}
+
+/// Parse the name of a preset, `mainnet` or `testnet`.
+impl FromStr for NetworkPreset {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match &*s.to_ascii_lowercase() {
+            "mainnet" => Ok(NetworkPreset::Mainnet),
+            "testnet" => Ok(NetworkPreset::Testnet),
+            _ => Err(format_err!("unknown network: {:?}", s)),
+        }
+    }
+}
+
+impl IntoIterator for NetworkPreset {
+    type Item = (Endpoint, AccountId);
+    type IntoIter = std::vec::IntoIter<(Endpoint, AccountId)>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.nodes().into_iter()
+    }
+}
+
+// The nodes a client can send requests to, with a transport for each
+pub(crate) struct Network {
+    nodes: Vec<AccountId>,
+    transports: HashMap<AccountId, Arc<dyn Transport>>,
+    // The transport for the address the client was created with, whose node is not known
+    // until it is set on the client
+    fallback: Option<Arc<dyn Transport>>,
+    // Nothing can be sent; the nodes are only picked to sign transactions for
+    offline: bool,
+    next: AtomicUsize,
+    health: HealthTracker,
+}
+
+impl Network {
+    pub(crate) fn new(
+        transports: HashMap<AccountId, Arc<dyn Transport>>,
+        fallback: Option<Arc<dyn Transport>>,
+    ) -> Self {
+        let mut nodes: Vec<_> = transports.keys().cloned().collect();
+        nodes.sort_by_key(|node| (node.shard, node.realm, node.account));
+
+        Self {
+            nodes,
+            transports,
+            fallback,
+            offline: false,
+            next: AtomicUsize::new(0),
+            health: HealthTracker::default(),
+        }
+    }
+
+    // A network of nodes that transactions can be signed for but never sent to
+    pub(crate) fn offline(mut nodes: Vec<AccountId>) -> Self {
+        nodes.sort_by_key(|node| (node.shard, node.realm, node.account));
+        nodes.dedup();
+
+        Self {
+            nodes,
+            transports: HashMap::new(),
+            fallback: None,
+            offline: true,
+            next: AtomicUsize::new(0),
+            health: HealthTracker::default(),
+        }
+    }
+
+    // Pick the node for the next request, round-robin, skipping nodes that are excluded
+    // for failing recently
+    pub(crate) fn select(&self) -> Option<AccountId> {
+        if self.nodes.is_empty() {
+            return None;
+        }
+
+        let start = self.next.fetch_add(1, Ordering::Relaxed);
+        let now = Instant::now();
+
+        (0..self.nodes.len())
+            .map(|offset| self.nodes[(start + offset) % self.nodes.len()])
+            .find(|node| !self.health.is_excluded(*node, now))
+            .or_else(|| {
+                // Every node is excluded; use the one whose exclusion ends first
+                self.nodes
+                    .iter()
+                    .cloned()
+                    .min_by_key(|node| self.health.excluded_until(*node))
+            })
+    }
+
+    // Track the health of a node from the precheck status of its response
+    pub(crate) fn responded(&self, node: AccountId, status: Status) {
+        if HealthTracker::is_node_failure(status) {
+            self.health.failure(node);
+        } else {
+            self.health.success(node);
+        }
+    }
+
+    // Track a request to the node that failed or timed out without a response
+    pub(crate) fn unreachable(&self, node: AccountId) {
+        self.health.failure(node);
+    }
+
+    pub(crate) fn health(&self) -> Vec<NodeHealth> {
+        self.health.snapshot(&self.nodes)
+    }
+
+    pub(crate) fn transport(&self, node: Option<AccountId>) -> Result<Arc<dyn Transport>, Error> {
+        if self.offline {
+            return Err(ErrorKind::Offline.into());
+        }
+
+        match node {
+            Some(node) => match self.transports.get(&node) {
+                Some(transport) => Ok(transport.clone()),
+
+                // A client for a single address, with no map of the network, sends to
+                // whichever node is set on it; a node missing from a map is never guessed
+                None if self.transports.is_empty() => self
+                    .fallback
+                    .clone()
+                    .ok_or_else(|| ErrorKind::UnknownNode(node).into()),
+
+                None => Err(ErrorKind::UnknownNode(node).into()),
+            },
+
+            None => self
+                .fallback
+                .clone()
+                .ok_or_else(|| ErrorKind::MissingField("node").into()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Endpoint, Network};
+    use crate::{proto, transport::Transport, AccountId, ErrorKind};
+    use failure::Error;
+    use futures::future::{self, BoxFuture, FutureExt};
+    use std::{collections::HashMap, sync::Arc, thread, time::Duration};
+
+    // A transport that is never sent anything
+    struct Unused;
+
+    impl Transport for Unused {
+        fn submit(
+            &self,
+            _: proto::Transaction::Transaction,
+            _: &[(String, String)],
+        ) -> BoxFuture<'static, Result<proto::TransactionResponse::TransactionResponse, Error>>
+        {
+            future::pending().boxed()
+        }
+
+        fn query(
+            &self,
+            _: proto::Query::Query,
+            _: &[(String, String)],
+        ) -> BoxFuture<'static, Result<proto::Response::Response, Error>> {
+            future::pending().boxed()
+        }
+    }
+
+    #[test]
+    fn test_transport() -> Result<(), Error> {
+        let (node3, node4) = (AccountId::new(0, 0, 3), AccountId::new(0, 0, 4));
+        let fallback: Arc<dyn Transport> = Arc::new(Unused);
+
+        let mut transports: HashMap<_, Arc<dyn Transport>> = HashMap::new();
+        transports.insert(node3, Arc::new(Unused));
+
+        // A network of nodes only sends to the nodes in it
+        let network = Network::new(transports, Some(fallback.clone()));
+        assert!(!Arc::ptr_eq(&network.transport(Some(node3))?, &fallback));
+
+        match network.transport(Some(node4)).err().unwrap().downcast_ref() {
+            Some(ErrorKind::UnknownNode(node)) => assert_eq!(*node, node4),
+            _ => panic!("expected node 0.0.4 to be unknown"),
+        }
+
+        // A client for a single address sends to whichever node is set on it
+        let network = Network::new(HashMap::new(), Some(fallback.clone()));
+        assert!(Arc::ptr_eq(&network.transport(Some(node4))?, &fallback));
+        assert!(Arc::ptr_eq(&network.transport(None)?, &fallback));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_select() {
+        let nodes: Vec<_> = (3..6).map(|node| AccountId::new(0, 0, node)).collect();
+        let network = Network::offline(nodes.clone());
+
+        // Round-robin over the nodes
+        let picked: Vec<_> = (0..4).filter_map(|_| network.select()).collect();
+        assert_eq!(picked, [nodes[0], nodes[1], nodes[2], nodes[0]]);
+
+        // skipping a failing node
+        network.unreachable(nodes[1]);
+        let picked: Vec<_> = (0..3).filter_map(|_| network.select()).collect();
+        assert_eq!(picked, [nodes[2], nodes[2], nodes[0]]);
+
+        // or, with every node failing, falling back to the one that failed first
+        thread::sleep(Duration::from_millis(1));
+        network.unreachable(nodes[2]);
+        network.unreachable(nodes[0]);
+        assert_eq!(network.select(), Some(nodes[1]));
+
+        assert_eq!(Network::offline(Vec::new()).select(), None);
+    }
+
+    fn tls(host: &str, port: u16) -> Endpoint {
+        Endpoint {
+            tls: true,
+            ..Endpoint::new(host, port)
+        }
+    }
+
+    #[test]
+    fn test_parse() -> Result<(), Error> {
+        assert_eq!(
+            "testnet.hedera.com:50131".parse::<Endpoint>()?,
+            Endpoint::new("testnet.hedera.com", 50131)
+        );
+
+        assert_eq!(
+            "35.237.200.180".parse::<Endpoint>()?,
+            Endpoint::new("35.237.200.180", 50211)
+        );
+
+        assert_eq!(
+            "https://testnet.hedera.com:50212/".parse::<Endpoint>()?,
+            tls("testnet.hedera.com", 50212)
+        );
+
+        assert_eq!(
+            "https://testnet.hedera.com/proto.CryptoService".parse::<Endpoint>()?,
+            tls("testnet.hedera.com", 50212)
+        );
+
+        assert_eq!(
+            "grpc://testnet.hedera.com/".parse::<Endpoint>()?,
+            Endpoint::new("testnet.hedera.com", 50211)
+        );
+
+        assert!("ftp://testnet.hedera.com".parse::<Endpoint>().is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_ipv6() -> Result<(), Error> {
+        assert_eq!("[::1]:50211".parse::<Endpoint>()?, Endpoint::new("::1", 50211));
+        assert_eq!("fd00::3".parse::<Endpoint>()?, Endpoint::new("fd00::3", 50211));
+        assert_eq!(
+            "grpc://[fd00::3]:50212".parse::<Endpoint>()?,
+            Endpoint::new("fd00::3", 50212)
+        );
+
+        assert!("[::1".parse::<Endpoint>().is_err());
+        assert!("host:port".parse::<Endpoint>().is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_display() -> Result<(), Error> {
+        assert_eq!(Endpoint::new("fd00::3", 50211).to_string(), "[fd00::3]:50211");
+        assert_eq!(
+            Endpoint::new("0.testnet.hedera.com", 50211).to_string(),
+            "0.testnet.hedera.com:50211"
+        );
+        assert_eq!(tls("fd00::3", 50212).to_string(), "https://[fd00::3]:50212");
+
+        Ok(())
+    }
+}
@@ -0,0 +1,71 @@
+use failure::{format_err, Error, ResultExt};
+
+// The width of the base64 lines in a PEM block, as written by OpenSSL
+const LINE_WIDTH: usize = 64;
+
+// Encode DER bytes as a PEM block with the given label, e.g. `PRIVATE KEY`
+pub(crate) fn encode(label: &str, der: &[u8]) -> String {
+    let encoded = base64::encode(der);
+    let mut pem = format!("-----BEGIN {}-----\n", label);
+
+    for line in encoded.as_bytes().chunks(LINE_WIDTH) {
+        // base64 is ASCII
+        pem.push_str(std::str::from_utf8(line).unwrap());
+        pem.push('\n');
+    }
+
+    pem.push_str(&format!("-----END {}-----\n", label));
+    pem
+}
+
+// Decode the DER bytes of the first PEM block with the given label; anything around the
+// block, such as the explanatory text OpenSSL can write, is ignored
+pub(crate) fn decode(label: &str, pem: &str) -> Result<Vec<u8>, Error> {
+    let begin = format!("-----BEGIN {}-----", label);
+    let end = format!("-----END {}-----", label);
+
+    let start = pem.find(&begin).ok_or_else(|| match pem.find("-----BEGIN ") {
+        Some(index) => format_err!(
+            "expected a PEM block of {}; found: {}",
+            label,
+            pem[index..].lines().next().unwrap_or_default()
+        ),
+
+        None => format_err!("expected a PEM block of {}", label),
+    })? + begin.len();
+
+    let length = pem[start..]
+        .find(&end)
+        .ok_or_else(|| format_err!("the PEM block of {} is not terminated", label))?;
+
+    let body = &pem[start..start + length];
+
+    if body.contains(':') {
+        // Headers, as used by legacy OpenSSL encryption, which is not supported
+        return Err(format_err!("the PEM block of {} has unsupported headers", label));
+    }
+
+    let encoded: String = body.split_whitespace().collect();
+
+    Ok(base64::decode(&encoded)
+        .with_context(|_| format!("the PEM block of {} is not valid base64", label))?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{decode, encode};
+    use failure::Error;
+
+    #[test]
+    fn test_round_trip() -> Result<(), Error> {
+        let der: Vec<u8> = (0..100).collect();
+        let pem = encode("PUBLIC KEY", &der);
+
+        assert!(pem.starts_with("-----BEGIN PUBLIC KEY-----\n"));
+        assert!(pem.lines().all(|line| line.len() <= 64));
+        assert_eq!(decode("PUBLIC KEY", &format!("comment\n{}", pem))?, der);
+        assert!(decode("PRIVATE KEY", &pem).is_err());
+
+        Ok(())
+    }
+}
@@ -6,35 +6,43 @@ mod query_crypto_get_account_balance;
 mod query_crypto_get_account_records;
 mod query_crypto_get_claim;
 mod query_crypto_get_info;
+mod query_crypto_get_stakers;
 mod query_file_get_contents;
 mod query_file_get_info;
 mod query_get_by_key;
+mod query_get_by_solidity_id;
+mod query_transaction_get_fast_record;
 mod query_transaction_get_receipt;
 mod query_transaction_get_record;
 
 pub use self::{
     query_contract_get_bytecode::*, query_contract_get_info::*, query_contract_get_records::*,
     query_contract_call::*, query_crypto_get_account_balance::*, query_crypto_get_account_records::*,
-    query_crypto_get_claim::*, query_crypto_get_info::*, query_file_get_contents::*,
-    query_file_get_info::*, query_get_by_key::*, query_transaction_get_receipt::*,
-    query_transaction_get_record::*,
+    query_crypto_get_claim::*, query_crypto_get_info::*, query_crypto_get_stakers::*,
+    query_file_get_contents::*, query_file_get_info::*, query_get_by_key::*,
+    query_get_by_solidity_id::*, query_transaction_get_fast_record::*,
+    query_transaction_get_receipt::*, query_transaction_get_record::*,
 };
 
 use crate::{
+    cache::{CachedEntity, CachedQuery, QueryCache},
+    functionality::query_parts,
+    interceptor::{self, Call, Interceptors, Request},
+    network::Network,
     proto::{
         self,
-        CryptoService_grpc::{CryptoService, CryptoServiceClient},
-        FileService_grpc::{FileService, FileServiceClient},
         Query::Query_oneof_query,
-        QueryHeader::QueryHeader,
-        SmartContractService_grpc::{SmartContractService, SmartContractServiceClient},
+        QueryHeader::{QueryHeader, ResponseType},
         ToProto,
     },
+    rate_limit::RateLimiter,
+    redact::Redacted,
+    retry::RetryPolicy,
+    timeout::await_response,
     transaction::{Transaction, TransactionCryptoTransfer},
     AccountId, Client, ErrorKind, SecretKey, Status,
 };
 use failure::Error;
-use futures::compat::Compat01As03;
 use futures::{Future};
 use std::{
     marker::PhantomData,
@@ -42,14 +50,22 @@ use std::{
         atomic::{AtomicUsize, Ordering},
         Arc,
     },
-    thread::sleep,
-    time::Duration,
+    time::{Duration, Instant},
 };
+use tokio::timer::delay;
+use tracing::field;
+use tracing_futures::Instrument;
 
 pub(crate) trait ToQueryProto {
     fn is_free(&self) -> bool {
         false
     }
+
+    // The key to cache responses to this query under, if it can be cached
+    fn cache_key(&self) -> Option<(CachedQuery, CachedEntity)> {
+        None
+    }
+
     fn to_query_proto(&self, header: QueryHeader) -> Result<Query_oneof_query, Error>;
 }
 
@@ -72,13 +88,19 @@ pub struct Query<T>
 where
     T: QueryResponse + Send + Sync + 'static,
 {
-    crypto_service: Arc<CryptoServiceClient>,
-    contract_service: Arc<SmartContractServiceClient>,
-    file_service: Arc<FileServiceClient>,
+    network: Arc<Network>,
     payment: Option<proto::Transaction::Transaction>,
     secret: Option<Arc<dyn Fn() -> Result<SecretKey, Error> + Send + Sync>>,
     operator: Option<AccountId>,
     node: Option<AccountId>,
+    max_payment: Option<u64>,
+    timeout: Option<Duration>,
+    retry: RetryPolicy,
+    // The fee of the payment transaction
+    default_fee: u64,
+    cache: Option<Arc<QueryCache>>,
+    rate_limiter: Option<Arc<RateLimiter>>,
+    interceptors: Arc<Interceptors>,
     inner: Box<dyn ToQueryProto + Send + Sync>,
     phantom: PhantomData<T>,
 }
@@ -93,12 +115,17 @@ where
     {
         Self {
             payment: None,
-            crypto_service: client.crypto.clone(),
-            contract_service: client.contract.clone(),
-            file_service: client.file.clone(),
+            network: client.network.clone(),
             node: client.node,
             operator: client.operator,
             secret: client.operator_secret.clone(),
+            max_payment: client.max_query_payment,
+            timeout: client.request_timeout,
+            retry: client.retry,
+            default_fee: client.default_fee,
+            cache: client.cache.clone(),
+            rate_limiter: client.rate_limiter.clone(),
+            interceptors: client.interceptors.clone(),
             inner: Box::new(inner),
             phantom: PhantomData,
         }
@@ -112,8 +139,43 @@ where
         Ok(self)
     }
 
+    /// Send this query to the given node, instead of one picked by the client.
+    pub fn node(&mut self, node: AccountId) -> &mut Self {
+        self.node = Some(node);
+        self
+    }
+
+    /// The maximum amount, in tinybars, that will be paid automatically for this query.
+    ///
+    /// Overrides the maximum set on the client. When set, the cost of the query is looked up
+    /// before paying for it and the query fails if the cost is above this amount. A payment
+    /// attached with `payment` is held to the same maximum.
+    pub fn max_payment(&mut self, max_payment: u64) -> &mut Self {
+        self.max_payment = Some(max_payment);
+        self
+    }
+
+    /// How long to wait for the node to respond before failing with `ErrorKind::Timeout`.
+    ///
+    /// Overrides the request timeout set on the client.
+    pub fn timeout(&mut self, timeout: Duration) -> &mut Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Ask the node how much it would charge, in tinybars, to answer this query.
+    pub async fn cost_async(&mut self) -> Result<u64, Error> {
+        self.cost_with(true).await
+    }
+
+    pub fn cost(&mut self) -> Result<u64, Error> {
+        crate::RUNTIME
+            .lock()
+            .block_on(self.cost_async())
+    }
+
     pub async fn get_async(&mut self) -> Result<T::Response, Error> {
-        T::get(self.send().await?.1)
+        self.get_with(true).await
     }
 
     pub fn get(&mut self) -> Result<T::Response, Error> {
@@ -122,8 +184,151 @@ where
             .block_on(self.get_async())
     }
 
+    /// Send the query, failing with `ErrorKind::RateLimited` instead of waiting if the rate
+    /// limiter of the client has no capacity for it.
+    pub async fn try_get_async(&mut self) -> Result<T::Response, Error> {
+        self.get_with(false).await
+    }
+
+    pub fn try_get(&mut self) -> Result<T::Response, Error> {
+        crate::RUNTIME
+            .lock()
+            .block_on(self.try_get_async())
+    }
+
+    // Unless `wait`, fail instead of waiting for the rate limiter
+    async fn cost_with(&mut self, wait: bool) -> Result<u64, Error> {
+        self.select_node();
+
+        // The node requires a payment transaction to be attached even when asking for the cost;
+        // a transfer of zero is accepted and is not charged
+        let payment = self.payment_transaction(0);
+        let query = self.to_query(ResponseType::COST_ANSWER, payment.as_ref());
+
+        Ok(self.send(query, wait).await?.0.get_cost())
+    }
+
+    async fn get_with(&mut self, wait: bool) -> Result<T::Response, Error> {
+        self.select_node();
+
+        let cache_key = self.inner.cache_key();
+
+        if let (Some(cache), Some((query, entity))) = (&self.cache, cache_key) {
+            if let Some(response) = cache.get(query, entity) {
+                return T::get(response);
+            }
+        }
+
+        if let Some(payment) = &self.payment {
+            // A payment attached by the caller is held to the maximum, if one is set
+            if let Some(max_payment) = self.max_payment {
+                let amount = payment_amount(payment);
+                if amount > max_payment {
+                    Err(ErrorKind::MaxQueryPaymentExceeded {
+                        query_cost: amount,
+                        max_query_payment: max_payment,
+                    })?;
+                }
+            }
+        } else if !self.inner.is_free() {
+            // Attach a payment transaction if this is a non-free query and we
+            // have payment details
+            self.payment = match self.max_payment {
+                Some(max_payment) => {
+                    let cost = self.cost_with(wait).await?;
+                    if cost > max_payment {
+                        Err(ErrorKind::MaxQueryPaymentExceeded {
+                            query_cost: cost,
+                            max_query_payment: max_payment,
+                        })?;
+                    }
+
+                    self.payment_transaction(cost)
+                }
+
+                None => self.payment_transaction(100_300_000),
+            };
+        }
+
+        let query = self.to_proto()?;
+        let response = self.send(Ok(query.clone()), wait).await?.1;
+
+        if let Some(cache) = &self.cache {
+            cache.observe(&query, &response);
+
+            if let Some((query, entity)) = cache_key {
+                cache.insert(query, entity, &response);
+            }
+        }
+
+        T::get(response)
+    }
+
+    // Attach a payment from the operator for exactly `cost` tinybars
+    pub(crate) fn pay(&mut self, cost: u64) -> &mut Self {
+        self.select_node();
+        self.payment = self.payment_transaction(cost);
+        self
+    }
+
+    // Pick the node this query is sent to, and paid to, unless one was already chosen
+    fn select_node(&mut self) {
+        if self.node.is_none() {
+            self.node = self.network.select();
+        }
+    }
+
+    fn payment_transaction(&self, cost: u64) -> Option<proto::Transaction::Transaction> {
+        if self.operator.is_none() || self.node.is_none() || self.secret.is_none() {
+            return None;
+        }
+
+        TransactionCryptoTransfer::new(&Client {
+            node: self.node.clone(),
+            operator: self.operator.clone(),
+            operator_secret: self.secret.clone(),
+            max_query_payment: self.max_payment,
+            request_timeout: self.timeout,
+            retry: self.retry,
+            default_fee: self.default_fee,
+            cache: self.cache.clone(),
+            rate_limiter: self.rate_limiter.clone(),
+            interceptors: self.interceptors.clone(),
+            network: self.network.clone(),
+        })
+        .transfer(*self.node.as_ref().unwrap(), cost as i64)
+        .transfer(*self.operator.as_ref().unwrap(), -(cost as i64))
+        .build()
+        .take_raw()
+        .ok()
+        .map(|tx| tx.tx)
+    }
+
+    fn to_query(
+        &self,
+        response_type: ResponseType,
+        payment: Option<&proto::Transaction::Transaction>,
+    ) -> Result<proto::Query::Query, Error> {
+        let mut header = proto::QueryHeader::QueryHeader::new();
+
+        header.set_responseType(response_type);
+
+        if let Some(payment) = payment {
+            header.set_payment(payment.clone());
+        } else if !self.inner.is_free() {
+            return Err(ErrorKind::MissingField("payment"))?;
+        }
+
+        let mut query = proto::Query::Query::new();
+        query.query = Some(self.inner.to_query_proto(header)?);
+
+        Ok(query)
+    }
+
     fn send(
-        &mut self,
+        &self,
+        query: Result<proto::Query::Query, Error>,
+        wait: bool,
     ) -> impl Future<
         Output = Result<
             (
@@ -135,71 +340,107 @@ where
     > {
         use self::proto::Query::Query_oneof_query::*;
 
-        if !self.inner.is_free() && self.payment.is_none() {
-            // Attach a payment transaction if this is a non-free query and we
-            // have payment details
-            if self.operator.is_some() && self.node.is_some() && self.secret.is_some() {
-                let cost = 100_300_000;
-                self.payment = TransactionCryptoTransfer::new(&Client {
-                    node: self.node.clone(),
-                    operator: self.operator.clone(),
-                    operator_secret: self.secret.clone(),
-                    crypto: self.crypto_service.clone(),
-                    file: self.file_service.clone(),
-                    contract: self.contract_service.clone(),
-                })
-                .transfer(*self.node.as_ref().unwrap(), cost as i64)
-                .transfer(*self.operator.as_ref().unwrap(), -(cost as i64))
-                .build()
-                .take_raw()
-                .ok()
-                .map(|tx| tx.tx);
+        let attempt = AtomicUsize::new(0);
+        let network = self.network.clone();
+        let rate_limiter = self.rate_limiter.clone();
+        let node = self.node;
+        let transport = self.network.transport(self.node);
+        let timeout = self.timeout;
+        let retry = self.retry;
+        let interceptors = self.interceptors.clone();
+
+        let span = tracing::info_span!(
+            "query",
+            kind = field::Empty,
+            response_type = field::Empty,
+            node = field::Empty,
+            attempt = field::Empty,
+            status = field::Empty,
+        );
+
+        if let Ok(query) = &query {
+            span.record("kind", &field::debug(query_parts(query).0));
+
+            if let Some(header) = query_parts(query).1 {
+                span.record("response_type", &field::debug(header.get_responseType()));
             }
         }
 
-        let attempt = AtomicUsize::new(0);
-        let crypto = self.crypto_service.clone();
-        let file = self.file_service.clone();
-        let contract = self.contract_service.clone();
-        let query_res: Option<Result<proto::Query::Query, _>> = Some(self.to_proto());
+        if let Some(node) = node {
+            span.record("node", &field::display(node));
+        }
+
+        let fields = span.clone();
+        let query_res: Option<Result<proto::Query::Query, _>> = Some(query);
 
         async move {
+            let transport = transport?;
+
+            // getByKey is defined as a query but no service on the node exposes it
+            if let Some(Ok(query)) = &query_res {
+                if let Some(getByKey(_)) = query.query {
+                    Err(ErrorKind::UnsupportedQuery)?;
+                }
+            }
+
             #[allow(clippy::never_loop)]
             loop {
                 break if let Some(Ok(query)) = &query_res {
                     if attempt.load(Ordering::SeqCst) == 0 {
-                        log::trace!("sent: {:#?}", query);
+                        tracing::trace!(query = ?Redacted(query), "sent");
                     }
 
-                    let query = query.clone();
-                    let o = grpc::RequestOptions::default();
-                    let response = match query.query {
-                        //////////////////////// CRYPTO QUERIES
-                        Some(cryptogetAccountBalance(_)) => crypto.crypto_get_balance(o, query),
-                        Some(cryptoGetInfo(_)) => crypto.get_account_info(o, query),
-                        Some(cryptoGetAccountRecords(_)) => crypto.get_account_records(o, query),
-                        //////////////////////// FILE QUERIES
-                        Some(fileGetInfo(_)) => file.get_file_info(o, query),
-                        Some(fileGetContents(_)) => file.get_file_content(o, query),
-                        //////////////////////// TRANSACTION QUERIES
-                        Some(transactionGetRecord(_)) => crypto.get_tx_record_by_tx_id(o, query),
-                        Some(transactionGetReceipt(_)) => crypto.get_transaction_receipts(o, query),
-                        //////////////////////// CONTRACT QUERIES
-                        Some(contractGetInfo(_)) => contract.get_contract_info(o, query),
-                        Some(contractGetBytecode(_)) => contract.contract_get_bytecode(o, query),
-                        Some(contractCallLocal(_)) => contract.contract_call_local_method(o, query),
-
-                        _ => unreachable!(),
+                    fields.record("attempt", &(attempt.load(Ordering::SeqCst) + 1));
+
+                    if let Some(rate_limiter) = &rate_limiter {
+                        rate_limiter.acquire(Request::Query(query), wait).await?;
+                    }
+
+                    let mut call = Call::new(
+                        Request::Query(query),
+                        node,
+                        attempt.load(Ordering::SeqCst) + 1,
+                    );
+
+                    interceptors.before_send(&mut call)?;
+
+                    let started = Instant::now();
+                    let response = transport.query(query.clone(), call.annotations());
+
+                    let mut response = match await_response(response, timeout).await {
+                        Ok(response) => {
+                            let outcome = interceptor::Response::Query(&response);
+                            interceptors.after_response(&call, outcome, started.elapsed());
+
+                            response
+                        }
+
+                        Err(error) => {
+                            let outcome = interceptor::Response::Error(&error);
+                            interceptors.after_response(&call, outcome, started.elapsed());
+
+                            if let Some(node) = node {
+                                network.unreachable(node);
+                            }
+
+                            return Err(error);
+                        }
                     };
 
-                    let mut response = Compat01As03::new(response.drop_metadata()).await?;
-                    log::trace!("recv: {:#?}", response);
+                    tracing::trace!(?response, "received");
 
                     let header = take_header(&mut response);
-                    match header.get_nodeTransactionPrecheckCode().into() {
-                        Status::Busy if attempt.load(Ordering::SeqCst) < 5 => {
+                    let status: Status = header.get_nodeTransactionPrecheckCode().into();
+                    fields.record("status", &field::debug(status));
+
+                    if let Some(node) = node {
+                        network.responded(node, status);
+                    }
+
+                    match status {
+                        Status::Busy if attempt.load(Ordering::SeqCst) < retry.max_retries => {
                             let attempt = attempt.fetch_add(1, Ordering::SeqCst) + 1;
-                            sleep(Duration::from_secs((attempt * 2) as u64));
+                            delay(Instant::now() + retry.delay(attempt)).await;
                             continue;
                         }
 
@@ -214,6 +455,7 @@ where
                 };
             }
         }
+        .instrument(span)
     }
 }
 
@@ -222,48 +464,231 @@ where
     T: QueryResponse + Send + Sync + 'static,
 {
     fn to_proto(&self) -> Result<proto::Query::Query, Error> {
-        let mut header = proto::QueryHeader::QueryHeader::new();
-
-        header.set_responseType(proto::QueryHeader::ResponseType::ANSWER_ONLY);
-
-        if let Some(payment) = &self.payment {
-            header.set_payment(payment.clone());
-        } else if !self.inner.is_free() {
-            return Err(ErrorKind::MissingField("payment"))?;
-        }
-
-        let mut query = proto::Query::Query::new();
-        query.query = Some(self.inner.to_query_proto(header)?);
-
-        Ok(query)
+        self.to_query(ResponseType::ANSWER_ONLY, self.payment.as_ref())
     }
 }
 
+// The amount a payment transaction transfers, in tinybars
+fn payment_amount(payment: &proto::Transaction::Transaction) -> u64 {
+    payment
+        .get_body()
+        .get_cryptoTransfer()
+        .get_transfers()
+        .get_accountAmounts()
+        .iter()
+        .filter(|transfer| transfer.get_amount() > 0)
+        .map(|transfer| transfer.get_amount() as u64)
+        .sum()
+}
+
 // this is needed because some times a query is responded to with the wrong
 // envelope type when an error occurs; this ensures we can get the error
 pub(crate) fn take_header(
     response: &mut proto::Response::Response,
 ) -> proto::ResponseHeader::ResponseHeader {
+    response_header(response).cloned().unwrap_or_default()
+}
+
+// The header of a response, whatever the type of its envelope
+pub(crate) fn response_header(
+    response: &proto::Response::Response,
+) -> Option<&proto::ResponseHeader::ResponseHeader> {
     use self::proto::Response::Response_oneof_response::*;
 
-    match &mut response.response {
-        Some(getByKey(ref mut res)) => res.take_header(),
-        Some(getBySolidityID(ref mut res)) => res.take_header(),
-        Some(contractCallLocal(ref mut res)) => res.take_header(),
-        Some(contractGetBytecodeResponse(ref mut res)) => res.take_header(),
-        Some(contractGetInfo(ref mut res)) => res.take_header(),
-        Some(contractGetRecordsResponse(ref mut res)) => res.take_header(),
-        Some(cryptogetAccountBalance(ref mut res)) => res.take_header(),
-        Some(cryptoGetAccountRecords(ref mut res)) => res.take_header(),
-        Some(cryptoGetInfo(ref mut res)) => res.take_header(),
-        Some(cryptoGetClaim(ref mut res)) => res.take_header(),
-        Some(cryptoGetProxyStakers(ref mut res)) => res.take_header(),
-        Some(fileGetContents(ref mut res)) => res.take_header(),
-        Some(fileGetInfo(ref mut res)) => res.take_header(),
-        Some(transactionGetReceipt(ref mut res)) => res.take_header(),
-        Some(transactionGetRecord(ref mut res)) => res.take_header(),
-        Some(transactionGetFastRecord(ref mut res)) => res.take_header(),
-
-        None => unreachable!(),
+    match &response.response {
+        Some(getByKey(res)) => res.header.as_ref(),
+        Some(getBySolidityID(res)) => res.header.as_ref(),
+        Some(contractCallLocal(res)) => res.header.as_ref(),
+        Some(contractGetBytecodeResponse(res)) => res.header.as_ref(),
+        Some(contractGetInfo(res)) => res.header.as_ref(),
+        Some(contractGetRecordsResponse(res)) => res.header.as_ref(),
+        Some(cryptogetAccountBalance(res)) => res.header.as_ref(),
+        Some(cryptoGetAccountRecords(res)) => res.header.as_ref(),
+        Some(cryptoGetInfo(res)) => res.header.as_ref(),
+        Some(cryptoGetClaim(res)) => res.header.as_ref(),
+        Some(cryptoGetProxyStakers(res)) => res.header.as_ref(),
+        Some(fileGetContents(res)) => res.header.as_ref(),
+        Some(fileGetInfo(res)) => res.header.as_ref(),
+        Some(transactionGetReceipt(res)) => res.header.as_ref(),
+        Some(transactionGetRecord(res)) => res.header.as_ref(),
+        Some(transactionGetFastRecord(res)) => res.header.as_ref(),
+
+        None => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::payment_amount;
+    use crate::{
+        functionality::query_parts,
+        proto::{self, QueryHeader::ResponseType},
+        transport::Transport,
+        AccountId, Client, ErrorKind, FileId, SecretKey,
+    };
+    use failure::Error;
+    use futures::future::{self, BoxFuture, FutureExt};
+    use parking_lot::Mutex;
+    use std::sync::Arc;
+
+    // Answers file contents queries at a fixed cost, keeping every query it is sent
+    struct Files {
+        cost: u64,
+        sent: Mutex<Vec<proto::Query::Query>>,
+    }
+
+    impl Transport for Arc<Files> {
+        fn submit(
+            &self,
+            _: proto::Transaction::Transaction,
+            _: &[(String, String)],
+        ) -> BoxFuture<'static, Result<proto::TransactionResponse::TransactionResponse, Error>>
+        {
+            unimplemented!()
+        }
+
+        fn query(
+            &self,
+            query: proto::Query::Query,
+            _: &[(String, String)],
+        ) -> BoxFuture<'static, Result<proto::Response::Response, Error>> {
+            let mut res = proto::FileGetContents::FileGetContentsResponse::new();
+
+            match query_parts(&query).1.map(|header| header.get_responseType()) {
+                Some(ResponseType::COST_ANSWER) => res.mut_header().set_cost(self.cost),
+                _ => res.mut_fileContents().set_contents(b"contents".to_vec()),
+            }
+
+            self.sent.lock().push(query);
+
+            let mut response = proto::Response::Response::new();
+            response.set_fileGetContents(res);
+
+            future::ready(Ok(response)).boxed()
+        }
+    }
+
+    fn client_with(
+        cost: u64,
+        max_query_payment: Option<u64>,
+    ) -> Result<(Client, Arc<Files>), Error> {
+        let (secret, _) = SecretKey::generate("");
+        let files = Arc::new(Files {
+            cost,
+            sent: Mutex::new(Vec::new()),
+        });
+
+        let mut builder = Client::builder("127.0.0.1:50211")
+            .node(AccountId::new(0, 0, 3))
+            .operator(AccountId::new(0, 0, 1001), move || secret.clone())
+            .transport(files.clone());
+
+        if let Some(max_query_payment) = max_query_payment {
+            builder = builder.max_query_payment(max_query_payment);
+        }
+
+        Ok((builder.build()?, files))
+    }
+
+    fn assert_exceeded(error: Error, cost: u64, max: u64) {
+        match error.downcast_ref() {
+            Some(ErrorKind::MaxQueryPaymentExceeded {
+                query_cost,
+                max_query_payment,
+            }) => assert_eq!((*query_cost, *max_query_payment), (cost, max)),
+
+            _ => panic!("expected the maximum payment to be exceeded, got: {}", error),
+        }
+    }
+
+    #[test]
+    fn test_max_payment() -> Result<(), Error> {
+        let (client, files) = client_with(500, Some(1_000))?;
+
+        assert_eq!(client.file(FileId::new(0, 0, 1001)).contents().get()?, b"contents");
+
+        // The cost is looked up, and then exactly that is paid
+        let sent = files.sent.lock();
+        assert_eq!(sent.len(), 2);
+
+        let header = query_parts(&sent[1]).1.unwrap();
+        assert_eq!(header.get_responseType(), ResponseType::ANSWER_ONLY);
+        assert_eq!(payment_amount(header.get_payment()), 500);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_max_payment_exceeded() -> Result<(), Error> {
+        let (client, files) = client_with(1_001, Some(1_000))?;
+
+        let error = client.file(FileId::new(0, 0, 1001)).contents().get().unwrap_err();
+        assert_exceeded(error, 1_001, 1_000);
+
+        // Nothing is paid once the cost is known to be too high
+        assert_eq!(files.sent.lock().len(), 1);
+
+        // The maximum of the query overrides the one of the client
+        let (client, _) = client_with(500, Some(1_000))?;
+        let mut query = client.file(FileId::new(0, 0, 1001)).contents();
+
+        assert_exceeded(query.max_payment(100).get().unwrap_err(), 500, 100);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_max_payment_default() -> Result<(), Error> {
+        // Without a maximum, the cost is not looked up and the usual fixed amount is paid
+        let (client, files) = client_with(100_300_001, None)?;
+
+        assert_eq!(client.file(FileId::new(0, 0, 1001)).contents().get()?, b"contents");
+
+        let sent = files.sent.lock();
+        assert_eq!(sent.len(), 1);
+
+        let header = query_parts(&sent[0]).1.unwrap();
+        assert_eq!(header.get_responseType(), ResponseType::ANSWER_ONLY);
+        assert_eq!(payment_amount(header.get_payment()), 100_300_000);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_max_payment_attached() -> Result<(), Error> {
+        let (client, files) = client_with(500, Some(1_000))?;
+
+        let mut payment = client.transfer_crypto();
+        payment
+            .transfer(AccountId::new(0, 0, 3), 2_000)
+            .transfer(AccountId::new(0, 0, 1001), -2_000);
+
+        // A payment attached by the caller is held to the maximum as well
+        let mut query = client.file(FileId::new(0, 0, 1001)).contents();
+        assert_exceeded(query.payment(&mut payment)?.get().unwrap_err(), 2_000, 1_000);
+
+        assert!(files.sent.lock().is_empty());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_unsupported_query() -> Result<(), Error> {
+        let (client, files) = client_with(500, None)?;
+        let (secret, _) = SecretKey::generate("");
+
+        // No service on the node answers a get-by-key query, so it is never sent
+        match client.entities_for_key(secret.public()).get() {
+            Err(error) => match error.downcast_ref() {
+                Some(ErrorKind::UnsupportedQuery) => {}
+                _ => panic!("expected the query to be unsupported, got: {}", error),
+            },
+
+            Ok(_) => panic!("expected the query to be unsupported"),
+        }
+
+        assert!(files.sent.lock().is_empty());
+
+        Ok(())
     }
 }
@@ -1,4 +1,5 @@
 use crate::{
+    cache::{CachedEntity, CachedQuery},
     proto::{self, Query::Query_oneof_query, QueryHeader::QueryHeader, ToProto},
     query::{Query, QueryResponse, ToQueryProto},
     Client, ContractId, ContractInfo,
@@ -28,6 +29,10 @@ impl QueryResponse for QueryContractGetInfo {
 }
 
 impl ToQueryProto for QueryContractGetInfo {
+    fn cache_key(&self) -> Option<(CachedQuery, CachedEntity)> {
+        Some((CachedQuery::ContractInfo, CachedEntity::Contract(self.contract)))
+    }
+
     fn to_query_proto(&self, header: QueryHeader) -> Result<Query_oneof_query, Error> {
         let mut query = proto::ContractGetInfo::ContractGetInfoQuery::new();
         query.set_header(header);
@@ -1,4 +1,5 @@
 use crate::{
+    cache::{CachedEntity, CachedQuery},
     proto::{self, Query::Query_oneof_query, QueryHeader::QueryHeader, ToProto},
     query::{Query, QueryResponse, ToQueryProto},
     AccountId, Client,
@@ -24,6 +25,10 @@ impl QueryResponse for QueryCryptoGetAccountBalance {
 }
 
 impl ToQueryProto for QueryCryptoGetAccountBalance {
+    fn cache_key(&self) -> Option<(CachedQuery, CachedEntity)> {
+        Some((CachedQuery::AccountBalance, CachedEntity::Account(self.account)))
+    }
+
     fn to_query_proto(&self, header: QueryHeader) -> Result<Query_oneof_query, Error> {
         let mut query = proto::CryptoGetAccountBalance::CryptoGetAccountBalanceQuery::new();
         query.set_header(header);
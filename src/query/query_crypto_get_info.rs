@@ -1,4 +1,5 @@
 use crate::{
+    cache::{CachedEntity, CachedQuery},
     proto::{self, Query::Query_oneof_query, QueryHeader::QueryHeader, ToProto},
     query::{Query, QueryResponse, ToQueryProto},
     AccountId, AccountInfo, Client,
@@ -25,6 +26,10 @@ impl QueryResponse for QueryCryptoGetInfo {
 }
 
 impl ToQueryProto for QueryCryptoGetInfo {
+    fn cache_key(&self) -> Option<(CachedQuery, CachedEntity)> {
+        Some((CachedQuery::AccountInfo, CachedEntity::Account(self.account)))
+    }
+
     fn to_query_proto(&self, header: QueryHeader) -> Result<Query_oneof_query, Error> {
         let mut query = proto::CryptoGetInfo::CryptoGetInfoQuery::new();
         query.set_header(header);
@@ -0,0 +1,41 @@
+use crate::{
+    proto::{self, Query::Query_oneof_query, QueryHeader::QueryHeader, ToProto},
+    query::{Query, QueryResponse, ToQueryProto},
+    AccountId, Client, ProxyStaker,
+};
+use failure::Error;
+use try_from::TryInto;
+
+pub struct QueryCryptoGetStakers {
+    account: AccountId,
+}
+
+impl QueryCryptoGetStakers {
+    pub fn new(client: &Client, account: AccountId) -> Query<Self> {
+        Query::new(client, Self { account })
+    }
+}
+
+impl QueryResponse for QueryCryptoGetStakers {
+    type Response = Vec<ProxyStaker>;
+
+    fn get(mut response: proto::Response::Response) -> Result<Self::Response, Error> {
+        response
+            .take_cryptoGetProxyStakers()
+            .take_stakers()
+            .take_proxyStaker()
+            .into_iter()
+            .map(TryInto::try_into)
+            .collect::<Result<Vec<_>, _>>()
+    }
+}
+
+impl ToQueryProto for QueryCryptoGetStakers {
+    fn to_query_proto(&self, header: QueryHeader) -> Result<Query_oneof_query, Error> {
+        let mut query = proto::CryptoGetStakers::CryptoGetStakersQuery::new();
+        query.set_header(header);
+        query.set_accountID(self.account.to_proto()?);
+
+        Ok(Query_oneof_query::cryptoGetProxyStakers(query))
+    }
+}
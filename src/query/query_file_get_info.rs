@@ -1,4 +1,5 @@
 use crate::{
+    cache::{CachedEntity, CachedQuery},
     proto::{self, Query::Query_oneof_query, QueryHeader::QueryHeader, ToProto},
     query::{Query, QueryResponse, ToQueryProto},
     Client, FileId, FileInfo,
@@ -25,6 +26,10 @@ impl QueryResponse for QueryFileGetInfo {
 }
 
 impl ToQueryProto for QueryFileGetInfo {
+    fn cache_key(&self) -> Option<(CachedQuery, CachedEntity)> {
+        Some((CachedQuery::FileInfo, CachedEntity::File(self.file)))
+    }
+
     fn to_query_proto(&self, header: QueryHeader) -> Result<Query_oneof_query, Error> {
         let mut query = proto::FileGetInfo::FileGetInfoQuery::new();
         query.set_header(header);
@@ -0,0 +1,86 @@
+use crate::{
+    error::ErrorKind,
+    proto::{self, Query::Query_oneof_query, QueryHeader::QueryHeader},
+    query::{Query, QueryResponse, ToQueryProto},
+    Client, ResolvedSolidityId,
+};
+use failure::Error;
+
+pub struct QueryGetBySolidityId {
+    solidity_id: String,
+}
+
+impl QueryGetBySolidityId {
+    pub fn new(client: &Client, solidity_id: String) -> Query<Self> {
+        Query::new(client, Self { solidity_id })
+    }
+}
+
+impl QueryResponse for QueryGetBySolidityId {
+    type Response = ResolvedSolidityId;
+
+    fn get(mut response: proto::Response::Response) -> Result<Self::Response, Error> {
+        Ok(response.take_getBySolidityID().into())
+    }
+}
+
+impl ToQueryProto for QueryGetBySolidityId {
+    fn to_query_proto(&self, header: QueryHeader) -> Result<Query_oneof_query, Error> {
+        // Solidity addresses are 20 bytes, hex encoded
+        if self.solidity_id.len() != 40 || hex::decode(&self.solidity_id).is_err() {
+            return Err(ErrorKind::Parse("{40 hex characters}"))?;
+        }
+
+        let mut query = proto::GetBySolidityID::GetBySolidityIDQuery::new();
+        query.set_header(header);
+        query.set_solidityID(self.solidity_id.clone());
+
+        Ok(Query_oneof_query::getBySolidityID(query))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::QueryGetBySolidityId;
+    use crate::{
+        proto::{Query::Query_oneof_query, QueryHeader::QueryHeader},
+        query::ToQueryProto,
+        ErrorKind,
+    };
+    use failure::Error;
+
+    fn to_query_proto(solidity_id: &str) -> Result<Query_oneof_query, Error> {
+        QueryGetBySolidityId {
+            solidity_id: solidity_id.to_owned(),
+        }
+        .to_query_proto(QueryHeader::new())
+    }
+
+    #[test]
+    fn test_solidity_id() -> Result<(), Error> {
+        let address = "00000000000000000000000000000000000003e9";
+
+        match to_query_proto(address)? {
+            Query_oneof_query::getBySolidityID(query) => {
+                assert_eq!(query.get_solidityID(), address)
+            }
+
+            _ => panic!("expected a getBySolidityID query"),
+        }
+
+        for invalid in &[
+            "",
+            "00000000000000000000000000000000000003e",
+            "00000000000000000000000000000000000003e9a",
+            "0x000000000000000000000000000000000003e9",
+            "00000000000000000000000000000000000003g9",
+        ] {
+            match to_query_proto(invalid).err().and_then(|error| error.downcast().ok()) {
+                Some(ErrorKind::Parse(_)) => {}
+                _ => panic!("expected {:?} to be rejected", invalid),
+            }
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,141 @@
+use crate::{
+    proto::{self, Query::Query_oneof_query, QueryHeader::QueryHeader, ToProto},
+    query::{Query, QueryResponse, ToQueryProto},
+    Client, TransactionId, TransactionRecord,
+};
+use failure::Error;
+use try_from::TryInto;
+
+pub struct QueryTransactionGetFastRecord {
+    transaction: TransactionId,
+}
+
+impl QueryTransactionGetFastRecord {
+    pub fn new(client: &Client, transaction: TransactionId) -> Query<Self> {
+        Query::new(client, Self { transaction })
+    }
+}
+
+impl QueryResponse for QueryTransactionGetFastRecord {
+    type Response = TransactionRecord;
+
+    fn get(mut response: proto::Response::Response) -> Result<Self::Response, Error> {
+        response
+            .take_transactionGetFastRecord()
+            .take_transactionRecord()
+            .try_into()
+    }
+}
+
+impl ToQueryProto for QueryTransactionGetFastRecord {
+    // Like receipts, fast records are kept for 180 seconds and are not charged for
+    fn is_free(&self) -> bool {
+        true
+    }
+
+    fn to_query_proto(&self, header: QueryHeader) -> Result<Query_oneof_query, Error> {
+        let mut query = proto::TransactionGetFastRecord::TransactionGetFastRecordQuery::new();
+        query.set_header(header);
+        query.set_transactionID(self.transaction.to_proto()?);
+
+        Ok(Query_oneof_query::transactionGetFastRecord(query))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::QueryTransactionGetFastRecord;
+    use crate::{
+        functionality::query_parts,
+        proto::{self, Query::Query_oneof_query, QueryHeader::QueryHeader},
+        query::ToQueryProto,
+        transport::Transport,
+        AccountId, Client, SecretKey, TransactionId,
+    };
+    use failure::Error;
+    use futures::future::{self, BoxFuture, FutureExt};
+    use parking_lot::Mutex;
+    use std::sync::Arc;
+
+    // Answers every query with the same record, keeping every query it is sent
+    #[derive(Default)]
+    struct Records(Mutex<Vec<proto::Query::Query>>);
+
+    impl Transport for Arc<Records> {
+        fn submit(
+            &self,
+            _: proto::Transaction::Transaction,
+            _: &[(String, String)],
+        ) -> BoxFuture<'static, Result<proto::TransactionResponse::TransactionResponse, Error>>
+        {
+            unimplemented!()
+        }
+
+        fn query(
+            &self,
+            query: proto::Query::Query,
+            _: &[(String, String)],
+        ) -> BoxFuture<'static, Result<proto::Response::Response, Error>> {
+            self.0.lock().push(query);
+
+            let mut res = proto::TransactionGetFastRecord::TransactionGetFastRecordResponse::new();
+            let record = res.mut_transactionRecord();
+            record.set_transactionHash(b"hash".to_vec());
+            record.set_memo("fast".to_owned());
+            record.set_transactionFee(100);
+            record.mut_transferList();
+
+            let mut response = proto::Response::Response::new();
+            response.set_transactionGetFastRecord(res);
+
+            future::ready(Ok(response)).boxed()
+        }
+    }
+
+    #[test]
+    fn test_fast_record() -> Result<(), Error> {
+        let transaction = TransactionId::new(AccountId::new(0, 0, 1001));
+        let query = QueryTransactionGetFastRecord {
+            transaction: transaction.clone(),
+        };
+
+        // Sent without a payment, like a receipt query
+        assert!(query.is_free());
+
+        match query.to_query_proto(QueryHeader::new())? {
+            Query_oneof_query::transactionGetFastRecord(query) => assert_eq!(
+                TransactionId::from(query.get_transactionID().clone()),
+                transaction
+            ),
+
+            _ => panic!("expected a transactionGetFastRecord query"),
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_fast_record_sent() -> Result<(), Error> {
+        let (secret, _) = SecretKey::generate("");
+        let records = Arc::new(Records::default());
+        let client = Client::builder("127.0.0.1:50211")
+            .node(AccountId::new(0, 0, 3))
+            .operator(AccountId::new(0, 0, 1001), move || secret.clone())
+            .transport(records.clone())
+            .build()?;
+
+        let transaction = TransactionId::new(AccountId::new(0, 0, 1001));
+        let record = client.transaction(transaction).fast_record().get()?;
+
+        assert_eq!(record.transaction_hash, b"hash");
+        assert_eq!(record.memo, "fast");
+        assert_eq!(record.transaction_fee, 100);
+
+        // Even with an operator to pay for it, the query is sent once and without a payment
+        let sent = records.0.lock();
+        assert_eq!(sent.len(), 1);
+        assert!(!query_parts(&sent[0]).1.unwrap().has_payment());
+
+        Ok(())
+    }
+}
@@ -1,9 +1,18 @@
 use crate::{
     proto::{self, Query::Query_oneof_query, QueryHeader::QueryHeader, ToProto},
     query::{Query, QueryResponse, ToQueryProto},
-    Client, TransactionId, TransactionReceipt,
+    Client, ErrorKind, Status, TransactionId, TransactionReceipt,
 };
+use chrono::{DateTime, Utc};
 use failure::Error;
+use futures::{stream, Stream};
+use std::time::{Duration, Instant};
+use tokio::timer::delay;
+
+// Receipts are kept by the network for 180 seconds after consensus
+const RECEIPT_PERIOD: Duration = Duration::from_secs(180);
+
+const RECEIPT_POLL_INTERVAL: Duration = Duration::from_millis(500);
 
 pub struct QueryTransactionGetReceipt {
     transaction_id: TransactionId,
@@ -36,3 +45,214 @@ impl ToQueryProto for QueryTransactionGetReceipt {
         Ok(Query_oneof_query::transactionGetReceipt(query))
     }
 }
+
+struct ReceiptUpdates {
+    query: Query<QueryTransactionGetReceipt>,
+    // The end of the receipt period, counted from the valid start of the transaction
+    deadline: DateTime<Utc>,
+    status: Option<Status>,
+    done: bool,
+}
+
+// Poll for the receipt of a transaction and yield it every time its status changes; the
+// stream ends after the first status that is not `Unknown`, or once the receipt period
+// has passed since the valid start of the transaction
+pub(crate) fn receipt_updates(
+    client: &Client,
+    transaction_id: TransactionId,
+) -> impl Stream<Item = Result<TransactionReceipt, Error>> {
+    // note: cannot fail; the period is far below the range of a chrono duration
+    let deadline = transaction_id.transaction_valid_start
+        + chrono::Duration::from_std(RECEIPT_PERIOD).unwrap();
+
+    let state = ReceiptUpdates {
+        query: QueryTransactionGetReceipt::new(client, transaction_id),
+        deadline,
+        status: None,
+        done: false,
+    };
+
+    stream::unfold(state, |mut state| {
+        async move {
+            loop {
+                if state.done {
+                    return None;
+                }
+
+                if Utc::now() > state.deadline {
+                    state.done = true;
+                    return Some((Err(ErrorKind::ReceiptTimeout.into()), state));
+                }
+
+                if state.status.is_some() {
+                    delay(Instant::now() + RECEIPT_POLL_INTERVAL).await;
+                }
+
+                match state.query.get_async().await {
+                    Ok(receipt) => {
+                        if state.status == Some(receipt.status) {
+                            continue;
+                        }
+
+                        state.status = Some(receipt.status);
+                        state.done = receipt.status != Status::Unknown;
+
+                        return Some((Ok(receipt), state));
+                    }
+
+                    Err(error) => {
+                        // The node may not know about the transaction for a moment after it
+                        // was submitted; keep polling as though its status were unknown
+                        if let Some(ErrorKind::PreCheck(Status::ReceiptNotFound)) =
+                            error.downcast_ref()
+                        {
+                            if state.status.is_none() {
+                                delay(Instant::now() + RECEIPT_POLL_INTERVAL).await;
+                            }
+
+                            continue;
+                        }
+
+                        state.done = true;
+                        return Some((Err(error), state));
+                    }
+                }
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        proto::{self, ResponseCode::ResponseCodeEnum},
+        transport::Transport,
+        AccountId, Client, ErrorKind, Status, TransactionId,
+    };
+    use chrono::{Duration, Utc};
+    use failure::Error;
+    use futures::{
+        future::{self, BoxFuture, FutureExt},
+        StreamExt,
+    };
+    use parking_lot::Mutex;
+    use std::collections::VecDeque;
+
+    // Answers each receipt query with the next of its statuses; `Err` for a status that
+    // fails pre-check
+    struct Receipts(Mutex<VecDeque<Result<ResponseCodeEnum, ResponseCodeEnum>>>);
+
+    impl Transport for Receipts {
+        fn submit(
+            &self,
+            _: proto::Transaction::Transaction,
+            _: &[(String, String)],
+        ) -> BoxFuture<'static, Result<proto::TransactionResponse::TransactionResponse, Error>>
+        {
+            unimplemented!()
+        }
+
+        fn query(
+            &self,
+            _: proto::Query::Query,
+            _: &[(String, String)],
+        ) -> BoxFuture<'static, Result<proto::Response::Response, Error>> {
+            let status = self.0.lock().pop_front().expect("no more receipts");
+
+            let mut res = proto::TransactionGetReceipt::TransactionGetReceiptResponse::new();
+            match status {
+                Ok(status) => res.mut_receipt().set_status(status),
+                Err(status) => res.mut_header().set_nodeTransactionPrecheckCode(status),
+            }
+
+            let mut response = proto::Response::Response::new();
+            response.set_transactionGetReceipt(res);
+
+            future::ready(Ok(response)).boxed()
+        }
+    }
+
+    fn updates(
+        valid_start: Duration,
+        statuses: Vec<Result<ResponseCodeEnum, ResponseCodeEnum>>,
+    ) -> Result<Vec<Result<Status, Error>>, Error> {
+        let client = Client::builder("127.0.0.1:50211")
+            .transport(Receipts(Mutex::new(statuses.into())))
+            .build()?;
+
+        let id = TransactionId {
+            account_id: AccountId::new(0, 0, 1001),
+            transaction_valid_start: Utc::now() - valid_start,
+        };
+
+        let updates = client.transaction(id).receipt_updates();
+        let updates = crate::RUNTIME.lock().block_on(updates.collect::<Vec<_>>());
+
+        Ok(updates
+            .into_iter()
+            .map(|receipt| receipt.map(|receipt| receipt.status))
+            .collect())
+    }
+
+    fn statuses(updates: Vec<Result<Status, Error>>) -> Vec<Status> {
+        updates.into_iter().map(Result::unwrap).collect()
+    }
+
+    #[test]
+    fn test_receipt_updates() -> Result<(), Error> {
+        use ResponseCodeEnum::{RECEIPT_NOT_FOUND, SUCCESS, UNKNOWN};
+
+        // Not yet known to the node, then waiting for consensus
+        let updates = updates(
+            Duration::seconds(10),
+            vec![Err(RECEIPT_NOT_FOUND), Ok(UNKNOWN), Ok(UNKNOWN), Ok(SUCCESS)],
+        )?;
+
+        assert_eq!(statuses(updates), [Status::Unknown, Status::Success]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_receipt_updates_final() -> Result<(), Error> {
+        use ResponseCodeEnum::INSUFFICIENT_ACCOUNT_BALANCE;
+
+        // Already at consensus, so there is no `Unknown` to yield first
+        let updates = updates(Duration::seconds(10), vec![Ok(INSUFFICIENT_ACCOUNT_BALANCE)])?;
+        assert_eq!(statuses(updates), [Status::InsufficientAccountBalance]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_receipt_updates_error() -> Result<(), Error> {
+        use ResponseCodeEnum::{INVALID_TRANSACTION_ID, UNKNOWN};
+
+        let mut updates = updates(
+            Duration::seconds(10),
+            vec![Ok(UNKNOWN), Err(INVALID_TRANSACTION_ID)],
+        )?;
+
+        assert_eq!(updates.len(), 2);
+        match updates.pop().unwrap().unwrap_err().downcast_ref() {
+            Some(ErrorKind::PreCheck(Status::InvalidTransactionId)) => {}
+            _ => panic!("expected the pre-check to fail"),
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_receipt_updates_timeout() -> Result<(), Error> {
+        // The receipt period is counted from the valid start, so nothing is sent
+        let updates = updates(Duration::minutes(4), vec![])?;
+
+        assert_eq!(updates.len(), 1);
+        match updates[0].as_ref().unwrap_err().downcast_ref() {
+            Some(ErrorKind::ReceiptTimeout) => {}
+            _ => panic!("expected the receipt period to have passed"),
+        }
+
+        Ok(())
+    }
+}
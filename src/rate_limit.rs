@@ -0,0 +1,218 @@
+use crate::{
+    functionality::{query_parts, transaction_functionality},
+    interceptor::Request,
+    proto::BasicTypes::HederaFunctionality,
+    ErrorKind,
+};
+use failure::Error;
+use parking_lot::Mutex;
+use std::{
+    collections::HashMap,
+    time::{Duration, Instant},
+};
+use tokio::timer;
+
+/// How many requests can be sent in a period of time.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RateLimit {
+    /// The number of requests that can be sent in each `period`, on average.
+    pub requests: u32,
+
+    pub period: Duration,
+
+    /// The number of requests that can be sent at once after the client has been idle.
+    pub burst: u32,
+}
+
+impl RateLimit {
+    /// Allow `requests` in each `period`, all of which can be sent at once.
+    pub fn new(requests: u32, period: Duration) -> Self {
+        Self {
+            requests,
+            period,
+            burst: requests,
+        }
+    }
+
+    pub fn per_second(requests: u32) -> Self {
+        Self::new(requests, Duration::from_secs(1))
+    }
+}
+
+// What a limit applies to
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+enum Limited {
+    Transactions,
+    Queries,
+    Functionality(HederaFunctionality),
+}
+
+// A token bucket; each request takes a token and tokens are added back at the rate of
+// the limit, up to its burst
+struct Bucket {
+    tokens: f64,
+    updated: Instant,
+}
+
+impl Bucket {
+    fn new(limit: RateLimit, now: Instant) -> Self {
+        Self {
+            tokens: f64::from(limit.burst.max(1)),
+            updated: now,
+        }
+    }
+
+    // Add the tokens earned since the last update and return how long until there is one
+    // to take
+    fn refill(&mut self, limit: RateLimit, now: Instant) -> Duration {
+        let rate = f64::from(limit.requests.max(1)) / limit.period.as_secs_f64();
+
+        if now > self.updated {
+            let earned = (now - self.updated).as_secs_f64() * rate;
+
+            self.tokens = (self.tokens + earned).min(f64::from(limit.burst.max(1)));
+            self.updated = now;
+        }
+
+        if self.tokens >= 1.0 {
+            Duration::from_secs(0)
+        } else {
+            Duration::from_secs_f64((1.0 - self.tokens) / rate)
+        }
+    }
+}
+
+/// Limits how fast the client sends requests, to stay under the throttles of the network
+/// instead of having requests rejected as `Busy`.
+///
+/// Transactions and queries are limited separately, and each `HederaFunctionality` can be
+/// given a limit of its own on top. Every request sent to a node counts, including retries
+/// and the cost queries sent before paying for a query. Requests without a limit are
+/// never held back.
+#[derive(Default)]
+pub struct RateLimiter {
+    limits: HashMap<Limited, RateLimit>,
+    buckets: Mutex<HashMap<Limited, Bucket>>,
+}
+
+impl RateLimiter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Limit every transaction.
+    pub fn transactions(mut self, limit: RateLimit) -> Self {
+        self.limits.insert(Limited::Transactions, limit);
+        self
+    }
+
+    /// Limit every query.
+    pub fn queries(mut self, limit: RateLimit) -> Self {
+        self.limits.insert(Limited::Queries, limit);
+        self
+    }
+
+    /// Limit the transactions or queries of one kind, in addition to the limit for every
+    /// transaction or query.
+    pub fn functionality(mut self, functionality: HederaFunctionality, limit: RateLimit) -> Self {
+        self.limits.insert(Limited::Functionality(functionality), limit);
+        self
+    }
+
+    // Take the capacity to send a request, waiting until there is some; unless `wait`, fail
+    // with `ErrorKind::RateLimited` instead of waiting
+    pub(crate) async fn acquire(&self, request: Request<'_>, wait: bool) -> Result<(), Error> {
+        let limited = match request {
+            Request::Transaction(tx) => [
+                Limited::Transactions,
+                Limited::Functionality(transaction_functionality(tx)),
+            ],
+
+            Request::Query(query) => [
+                Limited::Queries,
+                Limited::Functionality(query_parts(query).0),
+            ],
+        };
+
+        loop {
+            let delay = self.take(&limited, Instant::now());
+
+            if delay == Duration::from_secs(0) {
+                return Ok(());
+            }
+
+            if !wait {
+                return Err(ErrorKind::RateLimited(delay).into());
+            }
+
+            timer::delay(Instant::now() + delay).await;
+        }
+    }
+
+    // Take a token from every bucket, if each has one, or return how long until they do
+    fn take(&self, limited: &[Limited], now: Instant) -> Duration {
+        let mut buckets = self.buckets.lock();
+        let mut delay = Duration::from_secs(0);
+
+        for key in limited {
+            if let Some(limit) = self.limits.get(key) {
+                let bucket = buckets.entry(*key).or_insert_with(|| Bucket::new(*limit, now));
+
+                delay = delay.max(bucket.refill(*limit, now));
+            }
+        }
+
+        if delay == Duration::from_secs(0) {
+            for key in limited {
+                if let Some(bucket) = buckets.get_mut(key) {
+                    bucket.tokens -= 1.0;
+                }
+            }
+        }
+
+        delay
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Limited, RateLimit, RateLimiter};
+    use crate::proto::BasicTypes::HederaFunctionality;
+    use std::time::{Duration, Instant};
+
+    #[test]
+    fn test_take() {
+        let limiter = RateLimiter::new()
+            .transactions(RateLimit {
+                burst: 2,
+                ..RateLimit::per_second(10)
+            })
+            .functionality(HederaFunctionality::CryptoTransfer, RateLimit::per_second(1));
+
+        let transfer = [
+            Limited::Transactions,
+            Limited::Functionality(HederaFunctionality::CryptoTransfer),
+        ];
+
+        let create = [
+            Limited::Transactions,
+            Limited::Functionality(HederaFunctionality::CryptoCreate),
+        ];
+
+        let now = Instant::now();
+        let zero = Duration::from_secs(0);
+
+        assert_eq!(limiter.take(&transfer, now), zero);
+
+        // Transfers are limited on their own
+        assert_eq!(limiter.take(&transfer, now), Duration::from_secs(1));
+        assert_eq!(limiter.take(&create, now), zero);
+
+        // Every transaction shares a burst of 2
+        assert_eq!(limiter.take(&create, now), Duration::from_millis(100));
+        assert_eq!(limiter.take(&create, now + Duration::from_millis(100)), zero);
+
+        // Queries are not limited
+        assert_eq!(limiter.take(&[Limited::Queries], now), zero);
+    }
+}
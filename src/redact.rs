@@ -0,0 +1,107 @@
+use crate::proto::{self, QueryHeader::QueryHeader};
+use std::fmt;
+
+// Formats a request for logs without its signatures
+pub(crate) struct Redacted<'a, T>(pub(crate) &'a T);
+
+impl fmt::Debug for Redacted<'_, proto::Transaction::Transaction> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Transaction")
+            .field("body", self.0.get_body())
+            .field(
+                "sigs",
+                &Signatures(
+                    self.0.get_sigs().get_sigs().len() + self.0.get_sigMap().get_sigPair().len(),
+                ),
+            )
+            .finish()
+    }
+}
+
+impl fmt::Debug for Redacted<'_, proto::Query::Query> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut query = self.0.clone();
+
+        // The payment attached to a query is a signed transaction
+        if let Some(header) = query_header_mut(&mut query) {
+            if header.has_payment() {
+                header.mut_payment().clear_sigs();
+                header.mut_payment().clear_sigMap();
+            }
+        }
+
+        fmt::Debug::fmt(&query, f)
+    }
+}
+
+struct Signatures(usize);
+
+impl fmt::Debug for Signatures {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "[{} redacted]", self.0)
+    }
+}
+
+fn query_header_mut(query: &mut proto::Query::Query) -> Option<&mut QueryHeader> {
+    use self::proto::Query::Query_oneof_query::*;
+
+    match &mut query.query {
+        Some(getByKey(q)) => Some(q.mut_header()),
+        Some(getBySolidityID(q)) => Some(q.mut_header()),
+        Some(contractCallLocal(q)) => Some(q.mut_header()),
+        Some(contractGetInfo(q)) => Some(q.mut_header()),
+        Some(contractGetBytecode(q)) => Some(q.mut_header()),
+        Some(ContractGetRecords(q)) => Some(q.mut_header()),
+        Some(cryptogetAccountBalance(q)) => Some(q.mut_header()),
+        Some(cryptoGetAccountRecords(q)) => Some(q.mut_header()),
+        Some(cryptoGetInfo(q)) => Some(q.mut_header()),
+        Some(cryptoGetClaim(q)) => Some(q.mut_header()),
+        Some(cryptoGetProxyStakers(q)) => Some(q.mut_header()),
+        Some(fileGetContents(q)) => Some(q.mut_header()),
+        Some(fileGetInfo(q)) => Some(q.mut_header()),
+        Some(transactionGetReceipt(q)) => Some(q.mut_header()),
+        Some(transactionGetRecord(q)) => Some(q.mut_header()),
+        Some(transactionGetFastRecord(q)) => Some(q.mut_header()),
+        None => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Redacted;
+    use crate::proto;
+
+    #[test]
+    fn test_redact_transaction() {
+        let mut signature = proto::BasicTypes::Signature::new();
+        signature.set_ed25519(vec![0xAB; 64]);
+
+        let mut sigs = proto::BasicTypes::SignatureList::new();
+        sigs.mut_sigs().push(signature);
+
+        let mut tx = proto::Transaction::Transaction::new();
+        tx.set_sigs(sigs);
+
+        let formatted = format!("{:?}", Redacted(&tx));
+        assert!(formatted.contains("[1 redacted]"));
+        assert!(!formatted.contains("ed25519"));
+    }
+
+    #[test]
+    fn test_redact_query_payment() {
+        let mut signature = proto::BasicTypes::Signature::new();
+        signature.set_ed25519(vec![0xAB; 64]);
+
+        let mut payment = proto::Transaction::Transaction::new();
+        payment.mut_sigs().mut_sigs().push(signature);
+
+        let mut query = proto::CryptoGetAccountBalance::CryptoGetAccountBalanceQuery::new();
+        query.mut_header().set_payment(payment);
+
+        let mut wrapper = proto::Query::Query::new();
+        wrapper.set_cryptogetAccountBalance(query);
+
+        let formatted = format!("{:?}", Redacted(&wrapper));
+        assert!(!formatted.contains("ed25519"));
+    }
+}
@@ -0,0 +1,35 @@
+use std::time::Duration;
+
+/// How a query or transaction is retried when the node responds that it is busy.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RetryPolicy {
+    /// The number of times a request is retried before failing with the status of the node.
+    pub max_retries: usize,
+
+    /// How long to wait before the first retry. The wait grows by this much with each retry.
+    pub backoff: Duration,
+}
+
+impl RetryPolicy {
+    /// Never retry a request.
+    pub fn none() -> Self {
+        Self {
+            max_retries: 0,
+            backoff: Duration::from_secs(0),
+        }
+    }
+
+    // How long to wait before the given retry, counting from 1
+    pub(crate) fn delay(&self, retry: usize) -> Duration {
+        self.backoff * retry as u32
+    }
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_retries: 5,
+            backoff: Duration::from_secs(2),
+        }
+    }
+}
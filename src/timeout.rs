@@ -0,0 +1,55 @@
+use crate::ErrorKind;
+use failure::Error;
+use futures::Future;
+use std::time::Duration;
+use tokio::timer::Timeout;
+
+// Wait for the response to a request; if there is a deadline and the node has not
+// responded by then, fail with `ErrorKind::Timeout`
+pub(crate) async fn await_response<T>(
+    response: impl Future<Output = Result<T, Error>>,
+    timeout: Option<Duration>,
+) -> Result<T, Error> {
+    match timeout {
+        Some(timeout) => Ok(Timeout::new(response, timeout)
+            .await
+            .map_err(|_| ErrorKind::Timeout(timeout))??),
+
+        None => response.await,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::await_response;
+    use crate::ErrorKind;
+    use failure::Error;
+    use futures::future;
+    use std::time::Duration;
+
+    #[test]
+    fn test_await_response() -> Result<(), Error> {
+        let runtime = crate::RUNTIME.lock();
+        let timeout = Duration::from_millis(10);
+
+        let response = future::ready(Ok::<_, Error>(1));
+        assert_eq!(runtime.block_on(await_response(response, Some(timeout)))?, 1);
+
+        // A node that never responds
+        let response = future::pending::<Result<(), Error>>();
+        match runtime.block_on(await_response(response, Some(timeout))) {
+            Err(error) => match error.downcast_ref() {
+                Some(ErrorKind::Timeout(after)) => assert_eq!(*after, timeout),
+                _ => panic!("expected a timeout, got: {}", error),
+            },
+
+            Ok(()) => panic!("expected a timeout"),
+        }
+
+        // Without a deadline the response is awaited however long it takes
+        let response = future::ready(Ok::<_, Error>(2));
+        assert_eq!(runtime.block_on(await_response(response, None))?, 2);
+
+        Ok(())
+    }
+}
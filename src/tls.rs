@@ -0,0 +1,240 @@
+use failure::{bail, Error, ResultExt};
+use rustls::{
+    Certificate, RootCertStore, ServerCertVerified, ServerCertVerifier, TLSError,
+};
+use std::sync::Arc;
+use tls_api::{TlsConnector as _, TlsConnectorBuilder as _};
+use tls_api_rustls::TlsConnector;
+use webpki::DNSNameRef;
+
+// DER tags of the elements read from a certificate
+const TAG_SEQUENCE: u8 = 0x30;
+const TAG_BIT_STRING: u8 = 0x03;
+const TAG_VERSION: u8 = 0xa0;
+
+// How the connection to each node is secured
+#[derive(Clone, Default)]
+pub(crate) struct TlsConfig {
+    // DER-encoded certificates to trust in addition to the default roots
+    pub(crate) roots: Vec<Vec<u8>>,
+
+    // Accept a node only if its certificate carries the public key listed for it in the
+    // address book, instead of verifying it against the roots
+    pub(crate) pin: bool,
+}
+
+impl TlsConfig {
+    pub(crate) fn connector(&self, pinned_key: Option<&[u8]>) -> Result<TlsConnector, Error> {
+        let mut builder = TlsConnector::builder()?;
+
+        for root in &self.roots {
+            builder.add_root_certificate(tls_api::Certificate::from_der(root.clone()))?;
+        }
+
+        if let Some(key) = pinned_key {
+            builder
+                .underlying_mut()
+                .dangerous()
+                .set_certificate_verifier(Arc::new(PinnedKey(key.to_vec())));
+        }
+
+        Ok(builder.build()?)
+    }
+}
+
+// Decode the public key listed for a node in the address book: the hex of its DER-encoded
+// SubjectPublicKeyInfo
+pub(crate) fn pinned_key(key: &str) -> Result<Vec<u8>, Error> {
+    let key = hex::decode(key).context("public key is not hex")?;
+
+    match element(&key) {
+        Some((TAG_SEQUENCE, spki, contents, rest)) if rest.is_empty() && spki == &key[..] => {
+            match element(contents) {
+                Some((TAG_SEQUENCE, _, _, rest)) => match element(rest) {
+                    Some((TAG_BIT_STRING, _, _, rest)) if rest.is_empty() => {}
+                    _ => bail!("public key has no key bits"),
+                },
+
+                _ => bail!("public key has no algorithm"),
+            }
+        }
+
+        _ => bail!("public key is not a DER-encoded SubjectPublicKeyInfo"),
+    }
+
+    Ok(key)
+}
+
+// Verifies that the certificate of a node is for the public key it is listed with
+struct PinnedKey(Vec<u8>);
+
+impl ServerCertVerifier for PinnedKey {
+    fn verify_server_cert(
+        &self,
+        _roots: &RootCertStore,
+        presented_certs: &[Certificate],
+        _dns_name: DNSNameRef<'_>,
+        _ocsp_response: &[u8],
+    ) -> Result<ServerCertVerified, TLSError> {
+        let end_entity = presented_certs
+            .get(0)
+            .ok_or_else(|| TLSError::NoCertificatesPresented)?;
+
+        let spki = subject_public_key_info(&end_entity.0).ok_or_else(|| {
+            TLSError::General("certificate of the node could not be parsed".to_owned())
+        })?;
+
+        if spki == &self.0[..] {
+            Ok(ServerCertVerified::assertion())
+        } else {
+            Err(TLSError::General(
+                "certificate does not match the public key pinned for the node".to_owned(),
+            ))
+        }
+    }
+}
+
+// The DER encoding of the SubjectPublicKeyInfo of a certificate, as it appears in the
+// certificate; anything else that embeds a key, such as an extension, is not looked at
+fn subject_public_key_info(certificate: &[u8]) -> Option<&[u8]> {
+    // Certificate ::= SEQUENCE { tbsCertificate, signatureAlgorithm, signature }
+    let (tag, _, certificate, _) = element(certificate)?;
+    if tag != TAG_SEQUENCE {
+        return None;
+    }
+
+    // TBSCertificate ::= SEQUENCE { [0] version OPTIONAL, serialNumber, signature, issuer,
+    //                               validity, subject, subjectPublicKeyInfo, ... }
+    let (tag, _, tbs, _) = element(certificate)?;
+    if tag != TAG_SEQUENCE {
+        return None;
+    }
+
+    let mut fields = tbs;
+    if fields.first() == Some(&TAG_VERSION) {
+        fields = element(fields)?.3;
+    }
+
+    for _ in 0..5 {
+        fields = element(fields)?.3;
+    }
+
+    match element(fields)? {
+        (TAG_SEQUENCE, spki, _, _) => Some(spki),
+        _ => None,
+    }
+}
+
+// Split the DER element at the start of `der` into its tag, its encoding, its contents and
+// what follows it
+fn element(der: &[u8]) -> Option<(u8, &[u8], &[u8], &[u8])> {
+    let tag = *der.get(0)?;
+    let first = *der.get(1)?;
+
+    let (header, length) = if first < 0x80 {
+        (2, usize::from(first))
+    } else {
+        let count = usize::from(first & 0x7f);
+        if count == 0 || count > 4 {
+            return None;
+        }
+
+        let bytes = der.get(2..2 + count)?;
+        let length = bytes
+            .iter()
+            .fold(0, |length, byte| (length << 8) | usize::from(*byte));
+
+        (2 + count, length)
+    };
+
+    let end = header.checked_add(length)?;
+    let encoding = der.get(..end)?;
+
+    Some((tag, encoding, &encoding[header..], &der[end..]))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{pinned_key, PinnedKey};
+    use failure::Error;
+    use rustls::{Certificate, RootCertStore, ServerCertVerifier};
+    use webpki::DNSNameRef;
+
+    // Encode a DER element whose contents are shorter than 256 bytes
+    fn der(tag: u8, contents: &[&[u8]]) -> Vec<u8> {
+        let contents = contents.concat();
+        let mut der = match contents.len() {
+            length if length < 0x80 => vec![tag, length as u8],
+            length => vec![tag, 0x81, length as u8],
+        };
+
+        der.extend(contents);
+        der
+    }
+
+    fn spki(key: u8) -> Vec<u8> {
+        // AlgorithmIdentifier for Ed25519 and a BIT STRING of the key
+        let algorithm = der(0x30, &[&[0x06, 0x03, 0x2b, 0x65, 0x70]]);
+        let bits = der(0x03, &[&[0x00], &[key; 32]]);
+
+        der(0x30, &[&algorithm, &bits])
+    }
+
+    // A certificate for `subject_key` that also carries `extension_key` in an extension
+    fn certificate(subject_key: &[u8], extension_key: &[u8]) -> Vec<u8> {
+        let version = der(0xa0, &[&der(0x02, &[&[2]])]);
+        let serial = der(0x02, &[&[1]]);
+        let algorithm = der(0x30, &[&[0x06, 0x03, 0x2b, 0x65, 0x70]]);
+        let name = der(0x30, &[]);
+        let validity = der(0x30, &[]);
+        let extensions = der(0xa3, &[&der(0x04, &[extension_key])]);
+
+        let tbs = der(
+            0x30,
+            &[
+                &version, &serial, &algorithm, &name, &validity, &name, subject_key, &extensions,
+            ],
+        );
+
+        der(0x30, &[&tbs, &algorithm, &der(0x03, &[&[0x00]])])
+    }
+
+    fn verify(pinned: &[u8], certificate: Vec<u8>) -> bool {
+        PinnedKey(pinned.to_vec())
+            .verify_server_cert(
+                &RootCertStore::empty(),
+                &[Certificate(certificate)],
+                DNSNameRef::try_from_ascii_str("node").unwrap(),
+                &[],
+            )
+            .is_ok()
+    }
+
+    #[test]
+    fn test_pinned_key() -> Result<(), Error> {
+        let key = spki(1);
+        assert_eq!(pinned_key(&hex::encode(&key))?, key);
+
+        assert!(pinned_key("").is_err());
+        assert!(pinned_key("not hex").is_err());
+        assert!(pinned_key("3000").is_err());
+
+        // Trailing bytes after the key
+        assert!(pinned_key(&format!("{}00", hex::encode(&key))).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_verify_pinned_key() {
+        let (node, other) = (spki(1), spki(2));
+
+        assert!(verify(&node, certificate(&node, &other)));
+        assert!(!verify(&node, certificate(&other, &other)));
+
+        // The key of the node embedded anywhere but the subject public key is not enough
+        assert!(!verify(&node, certificate(&other, &node)));
+
+        assert!(!verify(&node, vec![0x30, 0x00]));
+    }
+}
@@ -24,23 +24,33 @@ pub use self::{
 };
 
 use crate::{
+    cache::QueryCache,
     crypto::SecretKey,
     error::ErrorKind,
-    proto::{
-        self,
-        CryptoService_grpc::{CryptoService, CryptoServiceClient},
-        FileService_grpc::{FileService, FileServiceClient},
-        SmartContractService_grpc::{SmartContractService, SmartContractServiceClient},
-        ToProto,
-    },
-    AccountId, Client, TransactionId,
+    functionality::transaction_functionality,
+    interceptor::{self, Call, Interceptors, Request},
+    network::Network,
+    proto::{self, ToProto},
+    rate_limit::RateLimiter,
+    redact::Redacted,
+    retry::RetryPolicy,
+    timeout::await_response,
+    AccountId, Client, Status, TransactionId,
 };
-use futures::compat::Compat01As03;
-use failure::Error;
+use failure::{format_err, Error};
 use futures::{Future,};
 use protobuf::Message;
 use query_interface::Object;
-use std::{any::Any, marker::PhantomData, mem::swap, sync::Arc, time::Duration};
+use std::{
+    any::Any,
+    marker::PhantomData,
+    mem::swap,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+use tokio::timer::delay;
+use tracing::field;
+use tracing_futures::Instrument;
 
 use crate::proto::TransactionBody::TransactionBody_oneof_data::*;
 
@@ -75,10 +85,13 @@ impl<T> TransactionKind<T> {
 }
 
 pub struct Transaction<T, S = TransactionBuilder<T>> {
-    crypto_service: Arc<CryptoServiceClient>,
-    file_service: Arc<FileServiceClient>,
-    contract_service: Arc<SmartContractServiceClient>,
+    network: Arc<Network>,
     secret: Option<Arc<dyn Fn() -> Result<SecretKey, Error> + Send + Sync>>,
+    timeout: Option<Duration>,
+    retry: RetryPolicy,
+    cache: Option<Arc<QueryCache>>,
+    rate_limiter: Option<Arc<RateLimiter>>,
+    interceptors: Arc<Interceptors>,
     kind: TransactionKind<T>,
     phantom: PhantomData<S>,
 }
@@ -89,16 +102,20 @@ impl<T: 'static> Transaction<T, TransactionBuilder<T>> {
         T: Object + ToProto<proto::TransactionBody::TransactionBody_oneof_data> + 'static,
     {
         Self {
-            crypto_service: client.crypto.clone(),
-            file_service: client.file.clone(),
-            contract_service: client.contract.clone(),
+            network: client.network.clone(),
             secret: client.operator_secret.clone(),
+            timeout: client.request_timeout,
+            retry: client.retry,
+            cache: client.cache.clone(),
+            rate_limiter: client.rate_limiter.clone(),
+            interceptors: client.interceptors.clone(),
             kind: TransactionKind::Builder(TransactionBuilder {
                 id: client.operator.map(TransactionId::new),
-                node: client.node,
+                // The node is part of the signed body so it is picked up front
+                node: client.node.or_else(|| client.network.select()),
                 memo: None,
                 inner: Box::<T>::new(inner) as Box<dyn Object>,
-                fee: 100_300_000,
+                fee: client.default_fee,
                 generate_record: false,
                 phantom: PhantomData,
             }),
@@ -156,6 +173,14 @@ impl<T: 'static> Transaction<T, TransactionBuilder<T>> {
         self.build().sign(secret)
     }
 
+    pub fn to_bytes(&mut self) -> Result<Vec<u8>, Error> {
+        self.build().to_bytes()
+    }
+
+    pub fn describe(&mut self) -> Result<String, Error> {
+        self.build().describe()
+    }
+
     pub fn execute_async(&mut self) -> impl Future<Output = Result<TransactionId, Error>> {
         self.build().execute_async()
     }
@@ -166,6 +191,16 @@ impl<T: 'static> Transaction<T, TransactionBuilder<T>> {
             .block_on(self.execute_async())
     }
 
+    pub fn try_execute_async(&mut self) -> impl Future<Output = Result<TransactionId, Error>> {
+        self.build().try_execute_async()
+    }
+
+    pub fn try_execute(&mut self) -> Result<TransactionId, Error> {
+        crate::RUNTIME
+            .lock()
+            .block_on(self.try_execute_async())
+    }
+
     #[inline]
     fn as_builder(&mut self) -> Option<&mut TransactionBuilder<T>> {
         match &mut self.kind {
@@ -260,22 +295,100 @@ impl<T: 'static> Transaction<T, TransactionRaw> {
         self
     }
 
+    /// The transaction as it would be sent to the node, signed by the operator, encoded as a
+    /// protobuf `Transaction`.
+    ///
+    /// The transaction is left as it is, so it can still be signed or executed.
+    pub fn to_bytes(&mut self) -> Result<Vec<u8>, Error> {
+        Ok(self.finished()?.write_to_bytes()?)
+    }
+
+    /// Describe the transaction as it would be sent to the node, including the signature of
+    /// the operator, with the signatures redacted; to be checked before it is executed.
+    pub fn describe(&mut self) -> Result<String, Error> {
+        Ok(format!("{:#?}", Redacted(&self.finished()?)))
+    }
+
     pub fn execute(&mut self) -> Result<TransactionId, Error> {
         crate::RUNTIME
             .lock()
             .block_on(self.execute_async())
     }
 
+    // A finished copy of the transaction, as `execute` would send it
+    fn finished(&self) -> Result<proto::Transaction::Transaction, Error> {
+        let mut state = match &self.kind {
+            TransactionKind::Raw(state) => TransactionRaw {
+                bytes: state.bytes.clone(),
+                tx: state.tx.clone(),
+            },
+
+            // The error stays with the transaction, to be returned again by `execute`
+            TransactionKind::Err(error) => {
+                return Err(match error.downcast_ref::<ErrorKind>() {
+                    Some(kind) => kind.clone().into(),
+                    None => format_err!("{}", error),
+                });
+            }
+
+            TransactionKind::Builder(_) => {
+                // not possible in safe rust
+                unreachable!()
+            }
+
+            TransactionKind::Empty => {
+                // should never be able to happen (in Rust)
+                panic!("transaction already executed")
+            }
+        };
+
+        self.finish(&mut state)?;
+
+        Ok(state.tx)
+    }
+
+    /// Execute the transaction, failing with `ErrorKind::RateLimited` instead of waiting if
+    /// the rate limiter of the client has no capacity for it.
+    pub fn try_execute(&mut self) -> Result<TransactionId, Error> {
+        crate::RUNTIME
+            .lock()
+            .block_on(self.try_execute_async())
+    }
+
     pub fn execute_async(&mut self) -> impl Future<Output = Result<TransactionId, Error>> {
-        let crypto = self.crypto_service.clone();
-        let file = self.file_service.clone();
-        let contract = self.contract_service.clone();
+        self.send(true)
+    }
+
+    pub fn try_execute_async(&mut self) -> impl Future<Output = Result<TransactionId, Error>> {
+        self.send(false)
+    }
+
+    // Send the transaction to its node; unless `wait`, fail instead of waiting for the rate
+    // limiter
+    fn send(&mut self, wait: bool) -> impl Future<Output = Result<TransactionId, Error>> {
+        let network = self.network.clone();
+        let rate_limiter = self.rate_limiter.clone();
+        let timeout = self.timeout;
+        let retry = self.retry;
+        let cache = self.cache.clone();
+        let interceptors = self.interceptors.clone();
         let state = self.take_raw();
 
+        let span = tracing::info_span!(
+            "transaction",
+            id = field::Empty,
+            node = field::Empty,
+            kind = field::Empty,
+            attempt = field::Empty,
+            status = field::Empty,
+        );
+
+        let fields = span.clone();
+
         async move {
             let state = state?;
 
-            let mut tx = state.tx;
+            let tx = state.tx;
             let id = tx
                 .get_body()
                 .transactionID
@@ -283,37 +396,93 @@ impl<T: 'static> Transaction<T, TransactionRaw> {
                 .unwrap()
                 .clone();
 
-            log::trace!(target: "hedera::transaction", "sent: {:#?}", tx);
-
-            let o = grpc::RequestOptions::default();
-            let response = match tx.mut_body().data {
-                //////////////////////// CRYPTO TRANSACTIONS
-                Some(cryptoCreateAccount(_)) => crypto.create_account(o, tx),
-                Some(cryptoUpdateAccount(_)) => crypto.update_account(o, tx),
-                Some(cryptoTransfer(_)) => crypto.crypto_transfer(o, tx),
-                Some(cryptoDeleteClaim(_)) => crypto.delete_claim(o, tx),
-                Some(cryptoDelete(_)) => crypto.crypto_delete(o, tx),
-                //////////////////////// FILE TRANSACTIONS
-                Some(fileCreate(_)) => file.create_file(o, tx),
-                Some(fileAppend(_)) => file.append_content(o, tx),
-                //////////////////////// CONTRACT TRANSACTIONS
-                Some(contractCreateInstance(_)) => contract.create_contract(o, tx),
-                Some(contractUpdateInstance(_)) => contract.update_contract(o, tx),
-                Some(contractDeleteInstance(_)) => contract.delete_contract(o, tx),
-                Some(contractCall(_)) => contract.contract_call_method(o, tx),
-
-                _ => unimplemented!(),
+            let node: AccountId = tx.get_body().get_nodeAccountID().clone().into();
+
+            fields.record("id", &field::display(TransactionId::from(id.clone())));
+            fields.record("node", &field::display(node));
+            fields.record("kind", &field::debug(transaction_functionality(&tx)));
+
+            let transport = network.transport(Some(node))?;
+
+            tracing::trace!(transaction = ?Redacted(&tx), "sent");
+
+            // Keep what the transaction changes so any cached responses about it can be
+            // invalidated once it is accepted, and again once it reaches consensus
+            let data = cache.as_ref().and_then(|_| tx.get_body().data.clone());
+
+            let mut retries = 0;
+            let response = loop {
+                fields.record("attempt", &(retries + 1));
+
+                if let Some(rate_limiter) = &rate_limiter {
+                    rate_limiter.acquire(Request::Transaction(&tx), wait).await?;
+                }
+
+                let mut call = Call::new(Request::Transaction(&tx), Some(node), retries + 1);
+                interceptors.before_send(&mut call)?;
+
+                let started = Instant::now();
+                let response = transport.submit(tx.clone(), call.annotations());
+
+                let response = match await_response(response, timeout).await {
+                    Ok(response) => {
+                        let outcome = interceptor::Response::Transaction(&response);
+                        interceptors.after_response(&call, outcome, started.elapsed());
+
+                        response
+                    }
+
+                    Err(error) => {
+                        let outcome = interceptor::Response::Error(&error);
+                        interceptors.after_response(&call, outcome, started.elapsed());
+
+                        network.unreachable(node);
+                        return Err(error);
+                    }
+                };
+
+                tracing::trace!(?response, "received");
+
+                let status: Status = response.get_nodeTransactionPrecheckCode().into();
+                fields.record("status", &field::debug(status));
+                network.responded(node, status);
+
+                // A busy node did not accept the transaction, so it is safe to send again
+                match status {
+                    Status::Busy if retries < retry.max_retries => {
+                        retries += 1;
+                        delay(Instant::now() + retry.delay(retries)).await;
+                    }
+
+                    _ => break response,
+                }
             };
 
-            let response = Compat01As03::new(response.drop_metadata()).await?;
-            log::trace!("recv: {:#?}", response);
+            try_precheck!(response).map(|_| {
+                let id = TransactionId::from(id);
+
+                if let Some(cache) = &cache {
+                    if let Some(data) = data {
+                        cache.submitted(&id, data);
+                    }
+                }
 
-            try_precheck!(response).map(|_| id.into())
+                id
+            })
         }
+        .instrument(span)
     }
 }
 
 impl<T: 'static, S: 'static> Transaction<T, S> {
+    /// How long to wait for the node to respond before failing with `ErrorKind::Timeout`.
+    ///
+    /// Overrides the request timeout set on the client.
+    pub fn timeout(&mut self, timeout: Duration) -> &mut Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
     #[inline]
     pub(crate) fn take_raw(&mut self) -> Result<TransactionRaw, Error> {
 //        use self::proto::Transaction::Transaction_oneof_bodyData::*;
@@ -322,67 +491,75 @@ impl<T: 'static, S: 'static> Transaction<T, S> {
             TransactionKind::Builder(_) => self.build().take_raw(),
 
             TransactionKind::Raw(mut state) => {
-                let tx = &mut state.tx;
+                self.finish(&mut state)?;
 
-                // note: cannot fail
-                let id = tx
-                    .get_body()
-                    .transactionID
-                    .as_ref()
-                    .unwrap()
-                    .clone();
+                Ok(state)
+            }
 
-                let operator = id.accountID.as_ref().unwrap().clone();
+            TransactionKind::Err(err) => Err(err),
 
-                if !tx.has_sigs() {
-                    // If .sign was never called this will be still need to be initialized
-                    tx.set_sigs(proto::BasicTypes::SignatureList::new());
-                }
+            TransactionKind::Empty => panic!("transaction already executed"),
+        }
+    }
 
-                if let Some(secret) = &self.secret {
-                    // HACK: If an accountNum is < 1000 pretend it has a slightly more complex key structure
-                    let signature = if operator.get_accountNum() < 1000 {
-                        (&[&secret()?.sign(&state.bytes)][..]).to_proto().unwrap()
-                    } else {
-                        secret()?.sign(&state.bytes).to_proto().unwrap()
-                    };
-
-                    match &tx.get_body().clone().data {
-                        Some(cryptoTransfer(data)) => {
-                            // Insert a signature for the operator if the operator
-                            // is sending any monies
-                            for transfer in &data.transfers.as_ref().unwrap().accountAmounts {
-                                if transfer.accountID.as_ref().unwrap() == &operator {
-                                    tx.sigs.as_mut().unwrap().sigs.push(signature.clone());
-                                }
-                            }
-                        }
+    // Sign as the operator and fill in what defaults to the operator; done once the
+    // transaction is about to be sent
+    fn finish(&self, state: &mut TransactionRaw) -> Result<(), Error> {
+        let tx = &mut state.tx;
 
-                        _ => {}
-                    }
+        // note: cannot fail
+        let id = tx
+            .get_body()
+            .transactionID
+            .as_ref()
+            .unwrap()
+            .clone();
 
-                    // Sign as the operator of the transaction
-                    tx.sigs.as_mut().unwrap().sigs.insert(0, signature);
-                }
+        let operator = id.accountID.as_ref().unwrap().clone();
+
+        if !tx.has_sigs() {
+            // If .sign was never called this will be still need to be initialized
+            tx.set_sigs(proto::BasicTypes::SignatureList::new());
+        }
 
-                match tx.mut_body().data {
-                    Some(cryptoDelete(ref mut data)) => {
-                        if !data.has_transferAccountID() {
-                            // default the transfer account ID to the operator of the transaction
-                            data.set_transferAccountID(operator);
+        if let Some(secret) = &self.secret {
+            // HACK: If an accountNum is < 1000 pretend it has a slightly more complex key structure
+            let signature = if operator.get_accountNum() < 1000 {
+                (&[&secret()?.sign(&state.bytes)][..]).to_proto().unwrap()
+            } else {
+                secret()?.sign(&state.bytes).to_proto().unwrap()
+            };
+
+            match &tx.get_body().clone().data {
+                Some(cryptoTransfer(data)) => {
+                    // Insert a signature for the operator if the operator
+                    // is sending any monies
+                    for transfer in &data.transfers.as_ref().unwrap().accountAmounts {
+                        if transfer.accountID.as_ref().unwrap() == &operator {
+                            tx.sigs.as_mut().unwrap().sigs.push(signature.clone());
                         }
                     }
-
-                    _ => {}
                 }
 
-                Ok(state)
+                _ => {}
             }
 
-            TransactionKind::Err(err) => Err(err),
+            // Sign as the operator of the transaction
+            tx.sigs.as_mut().unwrap().sigs.insert(0, signature);
+        }
 
-            TransactionKind::Empty => panic!("transaction already executed"),
+        match tx.mut_body().data {
+            Some(cryptoDelete(ref mut data)) => {
+                if !data.has_transferAccountID() {
+                    // default the transfer account ID to the operator of the transaction
+                    data.set_transferAccountID(operator);
+                }
+            }
+
+            _ => {}
         }
+
+        Ok(())
     }
 
     // Transition from builder to raw
@@ -0,0 +1,186 @@
+use crate::{
+    network::Endpoint,
+    proto::{
+        self,
+        CryptoService_grpc::{CryptoService, CryptoServiceClient},
+        FileService_grpc::{FileService, FileServiceClient},
+        SmartContractService_grpc::{SmartContractService, SmartContractServiceClient},
+    },
+    tls::TlsConfig,
+    ErrorKind,
+};
+use failure::{format_err, Error};
+use futures::{
+    compat::Compat01As03,
+    future::{self, BoxFuture},
+    FutureExt, TryFutureExt,
+};
+use grpc::ClientStub;
+use std::{net::ToSocketAddrs, sync::Arc};
+
+/// Sends requests to a node and returns its responses.
+///
+/// By default a client sends requests to each node over gRPC. Another transport can be
+/// given to `ClientBuilder::transport`, such as an in-memory ledger, a proxy or a different
+/// gRPC stack. Retries, timeouts and interceptors are handled by the client. The messages
+/// it carries are in `hedera::messages`.
+pub trait Transport: Send + Sync {
+    /// Submit a transaction to the node named in its body.
+    ///
+    /// `metadata` holds the annotations interceptors added to the request, to be sent
+    /// along with it.
+    fn submit(
+        &self,
+        tx: proto::Transaction::Transaction,
+        metadata: &[(String, String)],
+    ) -> BoxFuture<'static, Result<proto::TransactionResponse::TransactionResponse, Error>>;
+
+    /// Send a query to a node. A query that is paid for names the node in its payment.
+    fn query(
+        &self,
+        query: proto::Query::Query,
+        metadata: &[(String, String)],
+    ) -> BoxFuture<'static, Result<proto::Response::Response, Error>>;
+}
+
+// The gRPC services of a single connection to a node
+pub(crate) struct GrpcTransport {
+    crypto: CryptoServiceClient,
+    file: FileServiceClient,
+    contract: SmartContractServiceClient,
+}
+
+impl GrpcTransport {
+    // Connect over TLS unless no TLS configuration is given; with a pinned key, the node
+    // must present a certificate for that key
+    pub(crate) fn connect(
+        endpoint: &Endpoint,
+        http: httpbis::ClientConf,
+        tls: Option<&TlsConfig>,
+        pinned_key: Option<&[u8]>,
+    ) -> Result<Self, Error> {
+        let (host, port) = (&endpoint.host[..], endpoint.port);
+
+        let conf = grpc::ClientConf { http };
+        let inner = Arc::new(match tls {
+            Some(tls) => {
+                let connector = tls.connector(pinned_key)?;
+                let addr = (host, port)
+                    .to_socket_addrs()?
+                    .next()
+                    .ok_or_else(|| format_err!("no address found for {}", endpoint))?;
+
+                grpc::Client::new_expl(
+                    &addr,
+                    host,
+                    httpbis::ClientTlsOption::Tls(host.to_owned(), Arc::new(connector)),
+                    conf,
+                )?
+            }
+
+            None => grpc::Client::new_plain(host, port, conf)?,
+        });
+
+        Ok(Self {
+            crypto: CryptoServiceClient::with_client(inner.clone()),
+            file: FileServiceClient::with_client(inner.clone()),
+            contract: SmartContractServiceClient::with_client(inner),
+        })
+    }
+}
+
+impl Transport for GrpcTransport {
+    fn submit(
+        &self,
+        tx: proto::Transaction::Transaction,
+        metadata: &[(String, String)],
+    ) -> BoxFuture<'static, Result<proto::TransactionResponse::TransactionResponse, Error>> {
+        use self::proto::TransactionBody::TransactionBody_oneof_data::*;
+
+        let (crypto, file, contract) = (&self.crypto, &self.file, &self.contract);
+        let o = options(metadata);
+
+        let response = match tx.get_body().data {
+            //////////////////////// CRYPTO TRANSACTIONS
+            Some(cryptoCreateAccount(_)) => crypto.create_account(o, tx),
+            Some(cryptoUpdateAccount(_)) => crypto.update_account(o, tx),
+            Some(cryptoTransfer(_)) => crypto.crypto_transfer(o, tx),
+            Some(cryptoAddClaim(_)) => crypto.add_claim(o, tx),
+            Some(cryptoDeleteClaim(_)) => crypto.delete_claim(o, tx),
+            Some(cryptoDelete(_)) => crypto.crypto_delete(o, tx),
+            //////////////////////// FILE TRANSACTIONS
+            Some(fileCreate(_)) => file.create_file(o, tx),
+            Some(fileAppend(_)) => file.append_content(o, tx),
+            Some(fileUpdate(_)) => file.update_file(o, tx),
+            Some(fileDelete(_)) => file.delete_file(o, tx),
+            //////////////////////// CONTRACT TRANSACTIONS
+            Some(contractCreateInstance(_)) => contract.create_contract(o, tx),
+            Some(contractUpdateInstance(_)) => contract.update_contract(o, tx),
+            Some(contractDeleteInstance(_)) => contract.delete_contract(o, tx),
+            Some(contractCall(_)) => contract.contract_call_method(o, tx),
+
+            _ => return future::err(ErrorKind::UnsupportedTransaction.into()).boxed(),
+        };
+
+        single(response)
+    }
+
+    fn query(
+        &self,
+        query: proto::Query::Query,
+        metadata: &[(String, String)],
+    ) -> BoxFuture<'static, Result<proto::Response::Response, Error>> {
+        use self::proto::Query::Query_oneof_query::*;
+
+        let (crypto, file, contract) = (&self.crypto, &self.file, &self.contract);
+        let o = options(metadata);
+
+        let response = match query.query {
+            //////////////////////// CRYPTO QUERIES
+            Some(cryptogetAccountBalance(_)) => crypto.crypto_get_balance(o, query),
+            Some(cryptoGetInfo(_)) => crypto.get_account_info(o, query),
+            Some(cryptoGetAccountRecords(_)) => crypto.get_account_records(o, query),
+            Some(cryptoGetClaim(_)) => crypto.get_claim(o, query),
+            Some(cryptoGetProxyStakers(_)) => crypto.get_stakers_by_account_id(o, query),
+            //////////////////////// FILE QUERIES
+            Some(fileGetInfo(_)) => file.get_file_info(o, query),
+            Some(fileGetContents(_)) => file.get_file_content(o, query),
+            //////////////////////// TRANSACTION QUERIES
+            Some(transactionGetRecord(_)) => crypto.get_tx_record_by_tx_id(o, query),
+            Some(transactionGetReceipt(_)) => crypto.get_transaction_receipts(o, query),
+            Some(transactionGetFastRecord(_)) => crypto.get_fast_transaction_record(o, query),
+            //////////////////////// CONTRACT QUERIES
+            Some(contractGetInfo(_)) => contract.get_contract_info(o, query),
+            Some(contractGetBytecode(_)) => contract.contract_get_bytecode(o, query),
+            Some(contractCallLocal(_)) => contract.contract_call_local_method(o, query),
+            Some(ContractGetRecords(_)) => contract.get_tx_record_by_contract_id(o, query),
+            Some(getBySolidityID(_)) => contract.get_by_solidity_id(o, query),
+
+            // getByKey is defined as a query but no service on the node exposes it
+            _ => return future::err(ErrorKind::UnsupportedQuery.into()).boxed(),
+        };
+
+        single(response)
+    }
+}
+
+// Send annotations as gRPC metadata
+fn options(metadata: &[(String, String)]) -> grpc::RequestOptions {
+    let mut options = grpc::RequestOptions::default();
+
+    for (key, value) in metadata {
+        options
+            .metadata
+            .add(grpc::MetadataKey::from(&key[..]), value.clone().into_bytes().into());
+    }
+
+    options
+}
+
+fn single<T: Send + 'static>(
+    response: grpc::SingleResponse<T>,
+) -> BoxFuture<'static, Result<T, Error>> {
+    Compat01As03::new(response.drop_metadata())
+        .map_err(Error::from)
+        .boxed()
+}